@@ -1,23 +1,61 @@
+#[cfg(feature = "render")]
 use failure::{err_msg, Error};
-use sdl2::pixels::Color;
+#[cfg(feature = "render")]
 use sdl2::rect::Rect;
+#[cfg(feature = "render")]
 use sdl2::render::{Canvas, RenderTarget, Texture};
 
+#[cfg(feature = "render")]
 use resize::RenderContext;
 use shape::Circle;
+#[cfg(feature = "render")]
 use textures::{BallSprite, TextureMaker};
-use traits::{Collision, Renderable, Updatable};
-use utils::{Pixels, Point, Rad, Vector, PI};
+#[cfg(feature = "render")]
+use traits::Renderable;
+use traits::{Collision, Updatable};
+use utils::{Pixels, Point, Rad, Vec2};
 
 pub const BALL_RADIUS: Pixels = 8.0;
-const BALL_SPEED: f64 = 400.;
+/// How long a caught ball stays on the paddle if it is not released
+const CATCH_HOLD: f64 = 5.;
+
+/// How fast balls go: they start at `base` speed and get `increment` faster
+/// with each brick they hit, up to `max`. Speeds are in pixels per second.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Speed {
+    pub base: f64,
+    pub increment: f64,
+    pub max: f64,
+}
+
+impl Default for Speed {
+    fn default() -> Self {
+        Speed {
+            base: 400.,
+            increment: 4.,
+            max: 640.,
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct Ball {
     position: Point,
-    pub velocity: Vector,
-    color: sdl2::pixels::Color,
+    previous_position: Point,
+    pub velocity: Vec2,
     hold_timer: f64,
+    hold_offset: Pixels,
+    /// Bricks destroyed since the ball last touched the paddle
+    combo: u32,
+    speed: Speed,
+    /// Bricks hit since the ball was launched
+    hits: u32,
+    /// Slows the ball down, on top of its speed
+    slow: f64,
+    /// Divide bonus the ball came out of, while it still overlaps the other
+    /// balls of that split
+    split: Option<u32>,
 }
 
 impl Into<Circle> for &Ball {
@@ -27,15 +65,18 @@ impl Into<Circle> for &Ball {
 }
 
 impl Ball {
-    pub fn new(position: Point, angle: Rad) -> Ball {
+    pub fn new(position: Point, angle: Rad, speed: Speed) -> Ball {
         Ball {
             position,
-            velocity: Vector {
-                angle,
-                norm: BALL_SPEED,
-            },
+            previous_position: position,
+            velocity: Vec2::from_angle(angle) * speed.base,
             hold_timer: 3.,
-            color: Color::RGBA(120, 120, 200, 230),
+            hold_offset: 0.,
+            combo: 0,
+            speed,
+            hits: 0,
+            slow: 1.,
+            split: None,
         }
     }
 
@@ -43,21 +84,65 @@ impl Ball {
         self.into()
     }
 
-    pub fn speed(&mut self, n: usize) {
-        self.velocity.norm = BALL_SPEED / (n + 1) as f64;
+    /// Speed the ball up after hitting a brick
+    pub fn hit(&mut self) {
+        self.hits += 1;
+        self.update_speed();
+    }
+
+    /// Slow the ball down for each of the `n` Slow bonuses running
+    pub fn slow_down(&mut self, n: usize) {
+        self.slow = 1. / (n + 1) as f64;
+        self.update_speed();
+    }
+
+    fn update_speed(&mut self) {
+        let speed = self.speed.base + self.speed.increment * f64::from(self.hits);
+        self.velocity = self.velocity.normalize() * (speed.min(self.speed.max) * self.slow);
     }
 
     pub fn rotate(&mut self, angle: Rad) {
-        self.velocity.angle = (self.velocity.angle + angle) % 2. * PI;
+        self.velocity = self.velocity.rotate(angle);
     }
 
     pub fn bounce(&mut self, (normal, depth): Collision) {
-        self.velocity = self.velocity | normal;
-        self.position = self.position
-            + Point::from(Vector {
-                angle: normal,
-                norm: depth,
-            });
+        self.velocity = self.velocity.reflect(normal);
+        self.position = self.position + normal * depth;
+    }
+
+    /// Elastic collision with another ball of the same mass, `normal` pointing
+    /// from `other` to this ball. Both are pushed apart by half the overlap.
+    pub fn collide_ball(&mut self, other: &mut Ball, (normal, depth): Collision) {
+        self.position = self.position + normal * (depth / 2.);
+        other.position = other.position - normal * (depth / 2.);
+
+        // Swap the velocity components along the normal, if getting closer
+        let closing = (other.velocity - self.velocity).dot(normal);
+        if closing > 0. {
+            self.velocity = self.velocity + normal * closing;
+            other.velocity = other.velocity - normal * closing;
+        }
+    }
+
+    /// Push the ball out of a collision and send it off with the `angle`
+    /// heading, whatever it had before
+    pub fn deflect(&mut self, angle: Rad, (normal, depth): Collision) {
+        self.velocity = Vec2::from_angle(angle) * self.velocity.norm();
+        self.position = self.position + normal * depth;
+    }
+
+    /// Displacement of the ball over `dt` seconds
+    pub fn motion(&self, dt: f64) -> Point {
+        self.velocity * dt
+    }
+
+    /// Move the ball along its velocity for `dt` seconds
+    pub fn travel(&mut self, dt: f64) {
+        self.position = self.position + self.motion(dt);
+    }
+
+    pub fn position(&self) -> Point {
+        self.position
     }
 
     pub fn set_position(&mut self, p: Point) {
@@ -67,18 +152,57 @@ impl Ball {
     pub fn on_hold(&self) -> bool {
         self.hold_timer > 0.
     }
+
+    /// Horizontal position of the ball relative to the paddle while on hold
+    pub fn hold_offset(&self) -> Pixels {
+        self.hold_offset
+    }
+
+    /// Stick the ball to the paddle, `offset` pixels from its center. It will
+    /// leave with the `launch` angle once released.
+    pub fn catch(&mut self, offset: Pixels, launch: Rad) {
+        self.hold_timer = CATCH_HOLD;
+        self.hold_offset = offset;
+        self.velocity = Vec2::from_angle(launch) * self.velocity.norm();
+    }
+
+    pub fn split(&self) -> Option<u32> {
+        self.split
+    }
+
+    pub fn set_split(&mut self, split: Option<u32>) {
+        self.split = split;
+    }
+
+    pub fn combo(&self) -> u32 {
+        self.combo
+    }
+
+    pub fn count_brick(&mut self) {
+        self.combo += 1;
+    }
+
+    pub fn reset_combo(&mut self) {
+        self.combo = 0;
+    }
+
+    pub fn release(&mut self) {
+        self.hold_timer = 0.;
+    }
 }
 
+/// Only ticks the hold timer: the movement itself is resolved by the `State`,
+/// which sweeps the ball against the level through `Ball::travel`.
 impl Updatable for Ball {
     fn update(&mut self, dt: f64) {
+        self.previous_position = self.position;
         if self.on_hold() {
             self.hold_timer -= dt;
-        } else {
-            self.position = self.position + Point::from(self.velocity * dt);
         }
     }
 }
 
+#[cfg(feature = "render")]
 impl<T> Renderable<T> for Ball
 where
     T: RenderTarget,
@@ -92,7 +216,7 @@ where
         let copy_rects = TextureMaker::ball(
             BallSprite::Ball4,
             Rect::from_center(
-                context.translate_point(self.position),
+                context.translate_point(context.interpolate(self.previous_position, self.position)),
                 context.scale(BALL_RADIUS * 2.),
                 context.scale(BALL_RADIUS * 2.),
             ),
@@ -103,3 +227,31 @@ where
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod ball_test {
+    use super::*;
+
+    #[test]
+    fn test_speed() {
+        let speed = Speed {
+            base: 100.,
+            increment: 10.,
+            max: 120.,
+        };
+        let mut ball = Ball::new(Point::new(0., 0.), 0., speed);
+        assert_eq!(ball.velocity.norm(), 100.);
+
+        ball.hit();
+        assert_eq!(ball.velocity.norm(), 110.);
+        ball.slow_down(1);
+        assert_eq!(ball.velocity.norm(), 55.);
+
+        // Capped, then back to full speed once the Slow bonus is over
+        ball.hit();
+        ball.hit();
+        assert_eq!(ball.velocity.norm(), 60.);
+        ball.slow_down(0);
+        assert_eq!(ball.velocity.norm(), 120.);
+    }
+}
@@ -1,41 +1,91 @@
+#[cfg(feature = "render")]
 use failure::{err_msg, Error};
 use rand::distributions::{Distribution, Standard};
 use rand::Rng;
 
+#[cfg(feature = "render")]
 use sdl2::pixels::Color;
+#[cfg(feature = "render")]
 use sdl2::rect::Rect as SDLRect;
+#[cfg(feature = "render")]
 use sdl2::render::{Canvas, RenderTarget, Texture};
 
+#[cfg(feature = "render")]
 use resize::RenderContext;
 use shape::Circle;
-use traits::{Renderable, Updatable};
-use utils::Point;
+#[cfg(feature = "render")]
+use textures::{PowerUpSprite, PowerUpType, TextureMaker};
+#[cfg(feature = "render")]
+use traits::Renderable;
+use traits::Updatable;
+use utils::{Pixels, Point};
+
+const BONUS_RADIUS: Pixels = 12.;
+const BONUS_SPEED: f64 = 200.;
+/// Time each rotation frame of a capsule is shown, in seconds
+#[cfg(feature = "render")]
+const ROTATION_FRAME: f64 = 0.08;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum BonusType {
     Slow,
+    Catch,
+    Laser,
     Expand,
     Divide,
+    Break,
     Life,
 }
 
 impl Distribution<BonusType> for Standard {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> BonusType {
-        match rng.gen_range(0, 4) {
+        match rng.gen_range(0, 7) {
             0 => BonusType::Slow,
-            1 => BonusType::Expand,
-            2 => BonusType::Divide,
+            1 => BonusType::Catch,
+            2 => BonusType::Laser,
+            3 => BonusType::Expand,
+            4 => BonusType::Divide,
+            5 => BonusType::Break,
             _ => BonusType::Life,
         }
     }
 }
 
+#[cfg(feature = "render")]
 impl BonusType {
+    pub fn letter(self) -> char {
+        match self {
+            BonusType::Slow => 'S',
+            BonusType::Catch => 'C',
+            BonusType::Laser => 'L',
+            BonusType::Expand => 'E',
+            BonusType::Divide => 'D',
+            BonusType::Break => 'B',
+            BonusType::Life => 'P',
+        }
+    }
+
+    /// Capsule of the sprite sheet showing this bonus
+    pub fn power_up_type(self) -> PowerUpType {
+        match self {
+            BonusType::Slow => PowerUpType::S,
+            BonusType::Catch => PowerUpType::C,
+            BonusType::Laser => PowerUpType::L,
+            BonusType::Expand => PowerUpType::E,
+            BonusType::Divide => PowerUpType::D,
+            BonusType::Break => PowerUpType::B,
+            BonusType::Life => PowerUpType::P,
+        }
+    }
+
     pub fn color(self) -> Color {
         match self {
             BonusType::Slow => Color::RGBA(255, 0, 0, 255),
+            BonusType::Catch => Color::RGBA(255, 255, 0, 255),
+            BonusType::Laser => Color::RGBA(255, 0, 255, 255),
             BonusType::Expand => Color::RGBA(0, 255, 0, 255),
             BonusType::Divide => Color::RGBA(0, 0, 255, 255),
+            BonusType::Break => Color::RGBA(255, 128, 0, 255),
             BonusType::Life => Color::RGBA(0, 255, 255, 255),
         }
     }
@@ -45,27 +95,48 @@ impl BonusType {
 pub struct FallingBonus {
     pub bonus_type: BonusType,
     position: Point,
+    previous_position: Point,
+    /// Time since the bonus started falling, to animate it
+    age: f64,
 }
 
 impl Into<Circle> for &FallingBonus {
     fn into(self) -> Circle {
-        Circle::new(self.position, 12.)
+        Circle::new(self.position, BONUS_RADIUS)
     }
 }
 
 impl FallingBonus {
-    pub fn random(position: Point) -> Self {
+    pub fn random<R: Rng + ?Sized>(position: Point, rng: &mut R) -> Self {
         FallingBonus {
-            bonus_type: rand::random(),
+            bonus_type: rng.gen(),
             position,
+            previous_position: position,
+            age: 0.,
         }
     }
 
     pub fn shape(&self) -> Circle {
         self.into()
     }
+
+    #[cfg(feature = "render")]
+    fn sprite(&self) -> PowerUpSprite {
+        use textures::PowerUpSprite::*;
+        match (self.age / ROTATION_FRAME) as usize % 8 {
+            0 => Rot1,
+            1 => Rot2,
+            2 => Rot3,
+            3 => Rot4,
+            4 => Rot5,
+            5 => Rot6,
+            6 => Rot7,
+            _ => Rot8,
+        }
+    }
 }
 
+#[cfg(feature = "render")]
 impl<T> Renderable<T> for FallingBonus
 where
     T: RenderTarget,
@@ -74,15 +145,19 @@ where
         &self,
         canvas: &mut Canvas<T>,
         context: &RenderContext,
-        _texture: &Texture,
+        texture: &Texture,
     ) -> Result<(), Error> {
-        canvas.set_draw_color(self.bonus_type.color());
+        let copy_rects = TextureMaker::powerups(
+            self.sprite(),
+            self.bonus_type.power_up_type(),
+            SDLRect::from_center(
+                context.translate_point(context.interpolate(self.previous_position, self.position)),
+                context.scale(BONUS_RADIUS * 2.),
+                context.scale(BONUS_RADIUS * 2.),
+            ),
+        );
         canvas
-            .fill_rect(SDLRect::from_center(
-                context.translate_point(self.position),
-                context.scale(10.),
-                context.scale(10.),
-            ))
+            .copy(texture, copy_rects.src, copy_rects.dst)
             .map_err(err_msg)?;
         Ok(())
     }
@@ -90,10 +165,15 @@ where
 
 impl Updatable for FallingBonus {
     fn update(&mut self, dt: f64) {
-        self.position.y += dt * 200.;
+        self.previous_position = self.position;
+        self.position.y += dt * BONUS_SPEED;
+        self.age += dt;
     }
 }
 
+/// How long a timed bonus lasts
+const BONUS_DURATION: f64 = 10.;
+
 #[derive(Debug)]
 pub struct ActiveBonus {
     pub bonus_type: BonusType,
@@ -104,6 +184,11 @@ impl ActiveBonus {
     pub fn active(&self) -> bool {
         self.timer > 0.
     }
+
+    /// Fraction of the bonus time left, from 1 to 0
+    pub fn remaining(&self) -> f64 {
+        f64::max(self.timer / BONUS_DURATION, 0.)
+    }
 }
 impl From<&FallingBonus> for ActiveBonus {
     fn from(bonus: &FallingBonus) -> ActiveBonus {
@@ -115,7 +200,7 @@ impl From<BonusType> for ActiveBonus {
     fn from(bonus_type: BonusType) -> ActiveBonus {
         ActiveBonus {
             bonus_type,
-            timer: 10.,
+            timer: BONUS_DURATION,
         }
     }
 }
@@ -1,28 +1,147 @@
+#[cfg(feature = "render")]
 use failure::err_msg;
+#[cfg(feature = "render")]
 use sdl2::rect::Rect as SDLRect;
+#[cfg(feature = "render")]
 use sdl2::render::{Canvas, RenderTarget, Texture};
+#[cfg(feature = "render")]
 use textures::{BrickSprite, TextureMaker};
+#[cfg(feature = "render")]
 use traits::Renderable;
 
+#[cfg(feature = "render")]
 use resize::RenderContext;
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
 use shape::Rect;
 use utils::{Pixels, Point};
 
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum BrickType {
     Simple,
     Hard,
     Super,
+    /// Gets harder to break as the levels go by
+    Silver,
+    /// Indestructible
+    Gold,
 }
 
-#[derive(Clone, Deserialize, Serialize)]
+/// Levels sharing the same silver brick hitpoints
+const SILVER_LEVELS: u32 = 8;
+
+impl BrickType {
+    /// Points earned by destroying a brick of this type, unless the level
+    /// says otherwise
+    pub fn points(self) -> u32 {
+        match self {
+            BrickType::Simple => 50,
+            BrickType::Hard => 100,
+            BrickType::Super => 0,
+            BrickType::Silver => 200,
+            BrickType::Gold => 0,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Brick {
     pub center: Point,
     width: Pixels,
     height: Pixels,
     pub breakable: bool,
     hitpoints: u8,
+    points: u32,
+    kind: BrickType,
+}
+
+/// A brick as written in level files: either a `type` with an optional
+/// `hitpoints` override, or the raw `breakable` and `hitpoints` fields
+#[derive(Deserialize, Serialize)]
+struct BrickData {
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    brick_type: Option<BrickType>,
+    center: Point,
+    width: Pixels,
+    height: Pixels,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    breakable: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hitpoints: Option<u8>,
+}
+
+impl BrickData {
+    fn resolve(self) -> Result<Brick, String> {
+        let mut brick = match (self.brick_type, self.breakable) {
+            (Some(_), Some(_)) => {
+                return Err(String::from("`breakable` is set by the brick `type`"));
+            }
+            (Some(brick_type), None) => {
+                Brick::new(brick_type, self.center, self.width, self.height)
+            }
+            (None, Some(breakable)) => Brick {
+                center: self.center,
+                width: self.width,
+                height: self.height,
+                breakable,
+                hitpoints: 0,
+                points: 0,
+                // Type matching the fields, before any hit
+                kind: match (breakable, self.hitpoints) {
+                    (false, _) => BrickType::Super,
+                    (true, Some(hitpoints)) if hitpoints > 1 => BrickType::Hard,
+                    (true, _) => BrickType::Simple,
+                },
+            },
+            (None, None) => return Err(String::from("missing field `type`")),
+        };
+
+        match self.hitpoints {
+            Some(_) if !brick.breakable && self.brick_type.is_some() => {
+                Err(String::from("unbreakable bricks have no hitpoints"))
+            }
+            Some(hitpoints) => {
+                brick.hitpoints = hitpoints;
+                Ok(brick)
+            }
+            None if self.brick_type.is_none() => Err(String::from("missing field `hitpoints`")),
+            None => Ok(brick),
+        }
+    }
+}
+
+impl<'a> From<&'a Brick> for BrickData {
+    fn from(brick: &'a Brick) -> Self {
+        let brick_type = brick.brick_type();
+        let default = Brick::new(brick_type, brick.center, brick.width, brick.height);
+        BrickData {
+            brick_type: Some(brick_type),
+            center: brick.center,
+            width: brick.width,
+            height: brick.height,
+            breakable: None,
+            hitpoints: if !brick.breakable || brick.hitpoints == default.hitpoints {
+                None
+            } else {
+                Some(brick.hitpoints)
+            },
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Brick {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        BrickData::deserialize(deserializer)?
+            .resolve()
+            .map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for Brick {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        BrickData::from(self).serialize(serializer)
+    }
 }
 
 impl Into<Rect> for &Brick {
@@ -33,31 +152,58 @@ impl Into<Rect> for &Brick {
 
 impl Brick {
     pub fn new(brick_type: BrickType, center: Point, width: Pixels, height: Pixels) -> Brick {
-        match brick_type {
-            BrickType::Simple => Brick {
-                center,
-                width,
-                height,
-                breakable: true,
-                hitpoints: 1,
-            },
-            BrickType::Hard => Brick {
-                center,
-                width,
-                height,
-                breakable: true,
-                hitpoints: 2,
-            },
-            BrickType::Super => Brick {
-                center,
-                width,
-                height,
-                breakable: false,
-                hitpoints: 0,
-            },
+        let (breakable, hitpoints) = match brick_type {
+            BrickType::Simple => (true, 1),
+            BrickType::Hard => (true, 2),
+            BrickType::Silver => (true, 2),
+            BrickType::Super | BrickType::Gold => (false, 0),
+        };
+        Brick {
+            center,
+            width,
+            height,
+            breakable,
+            hitpoints,
+            points: 0,
+            kind: brick_type,
+        }
+    }
+
+    pub fn brick_type(&self) -> BrickType {
+        self.kind
+    }
+
+    /// Make silver bricks one hit harder every few levels, starting from the
+    /// first level
+    pub fn scale_hitpoints(&mut self, level_number: u32) {
+        if self.kind == BrickType::Silver {
+            let extra = level_number.saturating_sub(1) / SILVER_LEVELS;
+            self.hitpoints = self.hitpoints.saturating_add(extra as u8);
         }
     }
 
+    pub fn width(&self) -> Pixels {
+        self.width
+    }
+
+    pub fn height(&self) -> Pixels {
+        self.height
+    }
+
+    /// Top-left and bottom-right corners
+    pub fn bounds(&self) -> (Point, Point) {
+        let half = Point::new(self.width / 2., self.height / 2.);
+        (self.center - half, self.center + half)
+    }
+
+    pub fn points(&self) -> u32 {
+        self.points
+    }
+
+    pub fn set_points(&mut self, points: u32) {
+        self.points = points;
+    }
+
     pub fn alive(&self) -> bool {
         self.hitpoints > 0 || !self.breakable
     }
@@ -72,37 +218,29 @@ impl Brick {
         }
     }
 
+    #[cfg(feature = "render")]
     fn sprite(&self) -> BrickSprite {
         use textures::BrickSprite::*;
-        match self.hitpoints {
-            0 => White,
-            1 => LightRed,
-            2 => Orange,
-            3 => LightBlue,
-            4 => LightCyan,
-            5 => Magenta,
-            6 => LightGreen,
-            7 => Yellow,
-            8 => Blue,
-            9 => Red,
-            10 => Green,
-            11 => Cyan,
-            12 => Silver1,
-            13 => Silver2,
-            14 => Silver3,
-            15 => Silver4,
-            16 => Silver5,
-            17 => Silver6,
-            18 => Gold1,
-            19 => Gold2,
-            20 => Gold3,
-            21 => Gold4,
-            22 => Gold5,
-            _ => Gold6,
+        match (self.kind, self.hitpoints) {
+            (BrickType::Silver, _) => Silver1,
+            (BrickType::Gold, _) => Gold1,
+            (BrickType::Super, _) | (_, 0) => White,
+            (_, 1) => LightRed,
+            (_, 2) => Orange,
+            (_, 3) => LightBlue,
+            (_, 4) => LightCyan,
+            (_, 5) => Magenta,
+            (_, 6) => LightGreen,
+            (_, 7) => Yellow,
+            (_, 8) => Blue,
+            (_, 9) => Red,
+            (_, 10) => Green,
+            _ => Cyan,
         }
     }
 }
 
+#[cfg(feature = "render")]
 impl<T> Renderable<T> for Brick
 where
     T: RenderTarget,
@@ -127,3 +265,63 @@ where
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod brick_test {
+    use super::*;
+
+    fn load(json: &str) -> Result<Brick, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    #[test]
+    fn test_typed() {
+        let brick =
+            load(r#"{"type": "hard", "center": {"x": 0, "y": 0}, "width": 32, "height": 16}"#)
+                .unwrap();
+        assert_eq!(brick.brick_type(), BrickType::Hard);
+        assert_eq!(brick.hitpoints, 2);
+
+        let brick = load(
+            r#"{"type": "hard", "hitpoints": 5, "center": {"x": 0, "y": 0}, "width": 32, "height": 16}"#,
+        )
+        .unwrap();
+        assert_eq!(brick.hitpoints, 5);
+        let json = serde_json::to_string(&brick).unwrap();
+        assert!(json.contains(r#""type":"hard""#) && !json.contains("breakable"));
+        assert_eq!(load(&json).unwrap().hitpoints, 5);
+
+        assert!(load(
+            r#"{"type": "super", "hitpoints": 5, "center": {"x": 0, "y": 0}, "width": 32, "height": 16}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_silver_gold() {
+        let mut silver = Brick::new(BrickType::Silver, Point::new(0., 0.), 32., 16.);
+        silver.scale_hitpoints(8);
+        assert_eq!(silver.hitpoints, 2);
+        silver.scale_hitpoints(9);
+        assert_eq!(silver.hitpoints, 3);
+
+        let mut gold = Brick::new(BrickType::Gold, Point::new(0., 0.), 32., 16.);
+        gold.scale_hitpoints(20);
+        gold.damage();
+        assert!(gold.alive() && !gold.breakable);
+        assert_eq!(gold.brick_type(), BrickType::Gold);
+    }
+
+    #[test]
+    fn test_legacy() {
+        let brick = load(
+            r#"{"breakable": false, "hitpoints": 5, "center": {"x": 0, "y": 0}, "width": 32, "height": 16}"#,
+        )
+        .unwrap();
+        assert_eq!(brick.brick_type(), BrickType::Super);
+        let json = serde_json::to_string(&brick).unwrap();
+        assert_eq!(load(&json).unwrap().brick_type(), BrickType::Super);
+
+        assert!(load(r#"{"center": {"x": 0, "y": 0}, "width": 32, "height": 16}"#).is_err());
+    }
+}
@@ -0,0 +1,173 @@
+use utils::{Pixels, Point};
+
+/// Pairs of intervals that overlap, as indices into `intervals` with the
+/// smallest first. Intervals are sorted by their start, then each one is only
+/// checked against the following ones until they start past its end.
+pub fn sweep_and_prune(intervals: &[(Pixels, Pixels)]) -> Vec<(usize, usize)> {
+    let mut order: Vec<usize> = (0..intervals.len()).collect();
+    order.sort_by(|&a, &b| intervals[a].0.partial_cmp(&intervals[b].0).unwrap());
+
+    let mut pairs = Vec::new();
+    for (k, &i) in order.iter().enumerate() {
+        let end = intervals[i].1;
+        for &j in order[k + 1..]
+            .iter()
+            .take_while(|&&j| intervals[j].0 <= end)
+        {
+            pairs.push((i.min(j), i.max(j)));
+        }
+    }
+    pairs
+}
+
+/// Uniform grid over a set of boxes, given by their top-left and bottom-right
+/// corners. Each cell lists the boxes touching it.
+pub struct Grid {
+    origin: Point,
+    cell: Pixels,
+    columns: usize,
+    rows: usize,
+    cells: Vec<Vec<usize>>,
+}
+
+impl Grid {
+    pub fn new(boxes: &[(Point, Point)], cell: Pixels) -> Self {
+        let mut grid = Grid {
+            origin: Point::new(0., 0.),
+            cell,
+            columns: 0,
+            rows: 0,
+            cells: Vec::new(),
+        };
+        if boxes.is_empty() {
+            return grid;
+        }
+
+        let (min, max) = boxes.iter().fold(boxes[0], |(min, max), &(a, b)| {
+            (
+                Point::new(min.x.min(a.x), min.y.min(a.y)),
+                Point::new(max.x.max(b.x), max.y.max(b.y)),
+            )
+        });
+        grid.origin = min;
+        grid.columns = ((max.x - min.x) / cell).floor() as usize + 1;
+        grid.rows = ((max.y - min.y) / cell).floor() as usize + 1;
+        grid.cells = vec![Vec::new(); grid.columns * grid.rows];
+
+        for (i, &(a, b)) in boxes.iter().enumerate() {
+            if let Some((x0, y0, x1, y1)) = grid.span(a, b) {
+                for y in y0..=y1 {
+                    for x in x0..=x1 {
+                        grid.cells[y * grid.columns + x].push(i);
+                    }
+                }
+            }
+        }
+        grid
+    }
+
+    /// Cells covered by a box, clamped to the grid, or `None` if it is
+    /// outside of it
+    fn span(&self, min: Point, max: Point) -> Option<(usize, usize, usize, usize)> {
+        let cell = |v: Pixels, origin: Pixels, count: usize| {
+            ((v - origin) / self.cell)
+                .floor()
+                .max(0.)
+                .min(count as Pixels - 1.) as usize
+        };
+        let (x0, y0) = ((min.x - self.origin.x), (min.y - self.origin.y));
+        let (x1, y1) = ((max.x - self.origin.x), (max.y - self.origin.y));
+        let (width, height) = (
+            self.columns as Pixels * self.cell,
+            self.rows as Pixels * self.cell,
+        );
+        if self.cells.is_empty() || x1 < 0. || y1 < 0. || x0 > width || y0 > height {
+            return None;
+        }
+
+        Some((
+            cell(min.x, self.origin.x, self.columns),
+            cell(min.y, self.origin.y, self.rows),
+            cell(max.x, self.origin.x, self.columns),
+            cell(max.y, self.origin.y, self.rows),
+        ))
+    }
+
+    /// Take box `index`, between `min` and `max`, out of the cells it covers
+    pub fn remove(&mut self, index: usize, min: Point, max: Point) {
+        if let Some((x0, y0, x1, y1)) = self.span(min, max) {
+            for y in y0..=y1 {
+                for x in x0..=x1 {
+                    self.cells[y * self.columns + x].retain(|&i| i != index);
+                }
+            }
+        }
+    }
+
+    /// Boxes that may touch the box between `min` and `max`, in increasing
+    /// order
+    pub fn query(&self, min: Point, max: Point) -> Vec<usize> {
+        let mut found = Vec::new();
+        if let Some((x0, y0, x1, y1)) = self.span(min, max) {
+            for y in y0..=y1 {
+                for x in x0..=x1 {
+                    found.extend_from_slice(&self.cells[y * self.columns + x]);
+                }
+            }
+        }
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+}
+
+#[cfg(test)]
+mod broadphase_test {
+    use super::*;
+
+    #[test]
+    fn test_sweep_and_prune() {
+        let intervals = [(10., 20.), (0., 5.), (15., 30.), (4., 12.), (40., 50.)];
+        let mut pairs = sweep_and_prune(&intervals);
+        pairs.sort();
+        assert_eq!(pairs, vec![(0, 2), (0, 3), (1, 3)]);
+    }
+
+    #[test]
+    fn test_grid() {
+        let square = |x, y| (Point::new(x, y), Point::new(x + 10., y + 10.));
+        let boxes = [
+            square(0., 0.),
+            square(100., 0.),
+            square(95., 95.),
+            square(30., 60.),
+        ];
+        let grid = Grid::new(&boxes, 20.);
+
+        assert_eq!(
+            grid.query(Point::new(-5., -5.), Point::new(5., 5.)),
+            vec![0]
+        );
+        assert_eq!(
+            grid.query(Point::new(90., -50.), Point::new(200., 200.)),
+            vec![1, 2]
+        );
+        assert_eq!(
+            grid.query(Point::new(0., 0.), Point::new(105., 105.)),
+            vec![0, 1, 2, 3]
+        );
+        assert!(grid
+            .query(Point::new(300., 300.), Point::new(310., 310.))
+            .is_empty());
+
+        let mut grid = grid;
+        grid.remove(2, boxes[2].0, boxes[2].1);
+        assert_eq!(
+            grid.query(Point::new(0., 0.), Point::new(105., 105.)),
+            vec![0, 1, 3]
+        );
+        assert!(Grid::new(&[], 20.)
+            .query(Point::new(0., 0.), Point::new(10., 10.))
+            .is_empty());
+    }
+}
@@ -0,0 +1,282 @@
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+use failure::{err_msg, Error};
+
+use level::Level;
+use state::{Input, State};
+use traits::Updatable;
+
+/// List of level files, relative to the manifest, in the order they are played
+#[derive(Deserialize, Serialize)]
+struct Manifest {
+    levels: Vec<String>,
+}
+
+pub struct Campaign {
+    levels: Vec<Level>,
+    /// Files the levels were loaded from, if any
+    files: Vec<String>,
+}
+
+impl Campaign {
+    pub fn single(level: Level) -> Self {
+        Campaign {
+            levels: vec![level],
+            files: Vec::new(),
+        }
+    }
+
+    pub fn load_file(filename: &str) -> Result<Self, Error> {
+        let mut file = File::open(filename)?;
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+        let manifest: Manifest = serde_json::from_str(&contents).map_err(err_msg)?;
+
+        let root = Path::new(filename)
+            .parent()
+            .unwrap_or_else(|| Path::new(""));
+        let mut levels = Vec::new();
+        let mut files = Vec::new();
+        for level in &manifest.levels {
+            let path = root.join(level);
+            let path = path
+                .to_str()
+                .ok_or_else(|| err_msg(format!("Invalid level path {:?}", path)))?;
+            let mut level = Level::load_file(path)?;
+            level.set_number(levels.len() as u32 + 1);
+            levels.push(level);
+            files.push(path.to_string());
+        }
+
+        if levels.is_empty() {
+            return Err(err_msg("Campaign has no levels"));
+        }
+
+        Ok(Campaign { levels, files })
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.levels.is_empty()
+    }
+
+    pub fn level(&self, index: usize) -> Option<&Level> {
+        self.levels.get(index)
+    }
+
+    /// File the level was loaded from
+    pub fn file(&self, index: usize) -> Option<&str> {
+        self.files.get(index).map(String::as_str)
+    }
+}
+
+/// Level file along with its level, or why it could not be loaded
+pub type LevelCheck = (String, Result<Level, Error>);
+
+/// Load and validate every level file in `dir`, skipping campaign manifests.
+/// Gives the result for each file, sorted by name.
+pub fn validate_levels(dir: &str) -> Result<Vec<LevelCheck>, Error> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|e| e == "json" || e == "grid") {
+            let path = path
+                .to_str()
+                .ok_or_else(|| err_msg(format!("Invalid level path {:?}", path)))?;
+            files.push(path.to_string());
+        }
+    }
+    files.sort();
+
+    let mut results = Vec::new();
+    for file in files {
+        let mut contents = String::new();
+        File::open(&file)?.read_to_string(&mut contents)?;
+        if serde_json::from_str::<Manifest>(&contents).is_ok() {
+            continue;
+        }
+        let level = Level::load_file(&file);
+        results.push((file, level));
+    }
+    Ok(results)
+}
+
+/// A game running through a campaign. Once a level is cleared, the game waits
+/// for `next_level` to move on to the next one, carrying the lives over.
+pub struct Game {
+    campaign: Campaign,
+    current: usize,
+    state: State,
+    /// Make balls bounce off each other on every level
+    ball_collisions: bool,
+}
+
+impl Game {
+    pub fn new(campaign: Campaign, seed: u64) -> Self {
+        let state = State::new(campaign.levels[0].clone(), seed);
+        Game {
+            campaign,
+            current: 0,
+            state,
+            ball_collisions: false,
+        }
+    }
+
+    /// Turn ball collisions on for every level, or leave it to the levels
+    pub fn force_ball_collisions(&mut self, enabled: bool) {
+        self.ball_collisions = enabled;
+        self.apply_options();
+    }
+
+    pub fn ball_collisions_forced(&self) -> bool {
+        self.ball_collisions
+    }
+
+    fn apply_options(&mut self) {
+        let ball_collisions = self.ball_collisions || self.level().ball_collisions();
+        self.state.set_ball_collisions(ball_collisions);
+    }
+
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    /// Index of the level being played
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    pub fn level(&self) -> &Level {
+        &self.campaign.levels[self.current]
+    }
+
+    pub fn campaign(&self) -> &Campaign {
+        &self.campaign
+    }
+
+    pub fn input(&mut self, input: Input) {
+        self.state.input(input);
+    }
+
+    pub fn over(&self) -> bool {
+        !self.state.alive()
+    }
+
+    /// A level was cleared, and there is another one after it
+    pub fn level_cleared(&self) -> bool {
+        self.state.cleared() && self.current + 1 < self.campaign.len()
+    }
+
+    /// The last level of the campaign was cleared
+    pub fn victory(&self) -> bool {
+        self.state.cleared() && self.current + 1 == self.campaign.len()
+    }
+
+    pub fn next_level(&mut self) {
+        if self.level_cleared() {
+            self.current += 1;
+            self.state
+                .load_level(self.campaign.levels[self.current].clone());
+            self.apply_options();
+        }
+    }
+
+    /// Swap the current level for a new version of it. Either restart it from
+    /// scratch, or only replace its bricks and keep playing.
+    pub fn reload(&mut self, mut level: Level, bricks_only: bool) {
+        level.set_number(self.level().number());
+        if bricks_only {
+            self.state.patch_bricks(&level);
+        } else {
+            self.state = State::new(level.clone(), self.state.seed());
+            self.apply_options();
+        }
+        self.campaign.levels[self.current] = level;
+    }
+
+    /// Start over from the first level
+    pub fn restart(&mut self, seed: u64) {
+        self.current = 0;
+        self.state = State::new(self.campaign.levels[0].clone(), seed);
+        self.apply_options();
+    }
+}
+
+impl Updatable for Game {
+    fn update(&mut self, dt: f64) {
+        if self.over() || self.state.cleared() {
+            return;
+        }
+
+        self.state.update(dt);
+    }
+}
+
+#[cfg(test)]
+mod campaign_test {
+    use super::*;
+
+    #[test]
+    fn test_load_campaign() {
+        let campaign = Campaign::load_file("levels/campaign.json").unwrap();
+        assert!(campaign.len() > 1);
+    }
+
+    #[test]
+    fn test_validate_levels() {
+        let results = validate_levels("levels").unwrap();
+        assert!(!results.is_empty());
+        for (file, level) in results {
+            if let Err(e) = level {
+                panic!("{}: {}", file, e);
+            }
+        }
+    }
+
+    #[test]
+    fn test_progression() {
+        let mut empty = Level::default();
+        empty.bricks.clear();
+        let campaign = Campaign {
+            levels: vec![empty.clone(), Level::default(), empty],
+            files: Vec::new(),
+        };
+        let mut game = Game::new(campaign, 0);
+        assert_eq!(game.current(), 0);
+
+        game.update(0.01);
+        assert!(game.level_cleared());
+        assert_eq!(game.current(), 0);
+
+        game.next_level();
+        assert_eq!(game.current(), 1);
+        assert_eq!(game.state().lives(), 3);
+        assert!(!game.state().won());
+        assert!(!game.victory());
+
+        game.restart(1);
+        assert_eq!(game.current(), 0);
+        assert!(game.level_cleared());
+    }
+
+    #[test]
+    fn test_force_ball_collisions() {
+        let campaign = Campaign {
+            levels: vec![Level::default()],
+            files: Vec::new(),
+        };
+        let mut game = Game::new(campaign, 0);
+        assert!(!game.state().ball_collisions());
+
+        game.force_ball_collisions(true);
+        assert!(game.state().ball_collisions());
+        // Back to what the level says
+        game.force_ball_collisions(false);
+        assert!(!game.state().ball_collisions());
+    }
+}
@@ -0,0 +1,148 @@
+#[cfg(feature = "render")]
+use failure::{err_msg, Error};
+#[cfg(feature = "render")]
+use sdl2::rect::Rect as SDLRect;
+#[cfg(feature = "render")]
+use sdl2::render::{Canvas, RenderTarget, Texture};
+
+use brick::{Brick, BrickType};
+#[cfg(feature = "render")]
+use resize::RenderContext;
+#[cfg(feature = "render")]
+use textures::{BrickSprite, PropsSprite, TextureMaker};
+#[cfg(feature = "render")]
+use traits::Renderable;
+use traits::Updatable;
+use utils::{Pixels, Point};
+
+/// Number of frames in each animation
+const FRAMES: usize = 6;
+/// Time each frame is shown, in seconds
+const FRAME_TIME: f64 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EffectKind {
+    Explosion,
+    SilverFlash,
+    GoldFlash,
+}
+
+/// Short animation played where a brick was hit. Purely visual: it has no
+/// effect on the game.
+#[cfg_attr(not(feature = "render"), allow(dead_code))]
+pub struct Effect {
+    kind: EffectKind,
+    center: Point,
+    width: Pixels,
+    height: Pixels,
+    age: f64,
+}
+
+impl Effect {
+    fn new(kind: EffectKind, brick: &Brick) -> Self {
+        Effect {
+            kind,
+            center: brick.center,
+            width: brick.width(),
+            height: brick.height(),
+            age: 0.,
+        }
+    }
+
+    /// Explosion where `brick` was destroyed
+    pub fn explosion(brick: &Brick) -> Self {
+        Effect::new(EffectKind::Explosion, brick)
+    }
+
+    /// Flash over `brick` after a hit, for silver and gold bricks only
+    pub fn flash(brick: &Brick) -> Option<Self> {
+        match brick.brick_type() {
+            BrickType::Silver => Some(Effect::new(EffectKind::SilverFlash, brick)),
+            BrickType::Gold => Some(Effect::new(EffectKind::GoldFlash, brick)),
+            _ => None,
+        }
+    }
+
+    fn frame(&self) -> usize {
+        (self.age / FRAME_TIME) as usize
+    }
+
+    #[cfg(feature = "render")]
+    fn explosion_sprite(&self) -> PropsSprite {
+        use textures::PropsSprite::*;
+        match self.frame() {
+            0 => Explosion1,
+            1 => Explosion2,
+            2 => Explosion3,
+            3 => Explosion4,
+            4 => Explosion5,
+            _ => Explosion6,
+        }
+    }
+
+    #[cfg(feature = "render")]
+    fn flash_sprite(&self) -> BrickSprite {
+        use textures::BrickSprite::*;
+        match (self.kind, self.frame()) {
+            (EffectKind::GoldFlash, 0) => Gold1,
+            (EffectKind::GoldFlash, 1) => Gold2,
+            (EffectKind::GoldFlash, 2) => Gold3,
+            (EffectKind::GoldFlash, 3) => Gold4,
+            (EffectKind::GoldFlash, 4) => Gold5,
+            (EffectKind::GoldFlash, _) => Gold6,
+            (_, 0) => Silver1,
+            (_, 1) => Silver2,
+            (_, 2) => Silver3,
+            (_, 3) => Silver4,
+            (_, 4) => Silver5,
+            (_, _) => Silver6,
+        }
+    }
+
+    pub fn done(&self) -> bool {
+        self.frame() >= FRAMES
+    }
+}
+
+impl Updatable for Effect {
+    fn update(&mut self, dt: f64) {
+        self.age += dt;
+    }
+}
+
+#[cfg(feature = "render")]
+impl<T> Renderable<T> for Effect
+where
+    T: RenderTarget,
+{
+    fn render(
+        &self,
+        canvas: &mut Canvas<T>,
+        context: &RenderContext,
+        texture: &Texture,
+    ) -> Result<(), Error> {
+        let copy_rects = match self.kind {
+            // Square explosion, as large as the brick
+            EffectKind::Explosion => TextureMaker::props(
+                self.explosion_sprite(),
+                SDLRect::from_center(
+                    context.translate_point(self.center),
+                    context.scale(self.width),
+                    context.scale(self.width),
+                ),
+            ),
+            EffectKind::SilverFlash | EffectKind::GoldFlash => TextureMaker::brick(
+                self.flash_sprite(),
+                SDLRect::from_center(
+                    context.translate_point(self.center),
+                    context.scale(self.width),
+                    context.scale(self.height),
+                ),
+            ),
+        };
+        canvas
+            .copy(texture, copy_rects.src, copy_rects.dst)
+            .map_err(err_msg)?;
+        Ok(())
+    }
+}
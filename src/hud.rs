@@ -0,0 +1,124 @@
+use failure::{err_msg, Error};
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{Canvas, RenderTarget, Texture};
+
+use resize::RenderContext;
+use state::State;
+use text::draw_text;
+use textures::{TextureMaker, VesselSprite};
+use traits::Renderable;
+use utils::{Pixels, Point};
+
+/// Height of the band reserved above the level
+pub const HUD_HEIGHT: Pixels = 40.;
+
+const TEXT_SIZE: Pixels = 12.;
+const LIFE_WIDTH: Pixels = 24.;
+const LIFE_HEIGHT: Pixels = 6.;
+const BAR_WIDTH: Pixels = 48.;
+const BAR_HEIGHT: Pixels = 4.;
+const MARGIN: Pixels = 8.;
+
+/// Lives, score, level name and running bonuses, drawn above the level
+pub struct Hud<'a> {
+    state: &'a State,
+    level: &'a str,
+    width: Pixels,
+}
+
+impl<'a> Hud<'a> {
+    pub fn new(state: &'a State, level: &'a str, width: u32) -> Self {
+        Hud {
+            state,
+            level,
+            width: f64::from(width),
+        }
+    }
+}
+
+impl<'a, T> Renderable<T> for Hud<'a>
+where
+    T: RenderTarget,
+{
+    fn render(
+        &self,
+        canvas: &mut Canvas<T>,
+        context: &RenderContext,
+        texture: &Texture,
+    ) -> Result<(), Error> {
+        let white = Color::RGB(255, 255, 255);
+        let top = -HUD_HEIGHT + MARGIN + TEXT_SIZE / 2.;
+
+        // Lives, as small paddles on the left
+        for i in 0..self.state.lives() {
+            let center = Point::new(
+                MARGIN + LIFE_WIDTH / 2. + f64::from(i) * (LIFE_WIDTH + 4.),
+                top,
+            );
+            let copy_rects = TextureMaker::vessel(
+                VesselSprite::Size1,
+                Rect::from_center(
+                    context.translate_point(center),
+                    context.scale(LIFE_WIDTH),
+                    context.scale(LIFE_HEIGHT),
+                ),
+            );
+            canvas
+                .copy(texture, copy_rects.src, copy_rects.dst)
+                .map_err(err_msg)?;
+        }
+
+        // Level name in the middle…
+        draw_text(
+            canvas,
+            context,
+            Point::new(self.width / 2., top),
+            TEXT_SIZE,
+            self.level,
+            white,
+        )?;
+
+        // …and the score on the right
+        let score = format!("{:08}", self.state.score());
+        draw_text(
+            canvas,
+            context,
+            Point::new(
+                self.width - MARGIN - TEXT_SIZE * score.len() as f64 / 2.,
+                top,
+            ),
+            TEXT_SIZE,
+            &score,
+            white,
+        )?;
+
+        // Running bonuses, with a bar showing the time left
+        let bottom = -MARGIN - BAR_HEIGHT / 2.;
+        for (i, bonus) in self.state.active_bonuses().iter().enumerate() {
+            let left = MARGIN + i as f64 * (TEXT_SIZE + BAR_WIDTH + MARGIN);
+            let color = bonus.bonus_type.color();
+            draw_text(
+                canvas,
+                context,
+                Point::new(left + TEXT_SIZE / 4., bottom),
+                TEXT_SIZE / 2.,
+                &bonus.bonus_type.letter().to_string(),
+                color,
+            )?;
+
+            let width = BAR_WIDTH * bonus.remaining();
+            let center = Point::new(left + TEXT_SIZE + width / 2., bottom);
+            canvas.set_draw_color(color);
+            canvas
+                .fill_rect(Rect::from_center(
+                    context.translate_point(center),
+                    context.scale(width),
+                    context.scale(BAR_HEIGHT),
+                ))
+                .map_err(err_msg)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+#[cfg(feature = "render")]
+use failure::{err_msg, Error};
+#[cfg(feature = "render")]
+use sdl2::rect::Rect as SDLRect;
+#[cfg(feature = "render")]
+use sdl2::render::{Canvas, RenderTarget, Texture};
+
+#[cfg(feature = "render")]
+use resize::RenderContext;
+use shape::Rect;
+#[cfg(feature = "render")]
+use textures::{RocketSprite, TextureMaker};
+#[cfg(feature = "render")]
+use traits::Renderable;
+use traits::{Collide, Collision, Updatable};
+use utils::{Pixels, Point};
+
+pub const BOLT_WIDTH: Pixels = 6.0;
+pub const BOLT_HEIGHT: Pixels = 15.0;
+const BOLT_SPEED: f64 = 600.;
+
+/// Projectile shot upwards by the paddle while the Laser bonus is active
+#[derive(Clone)]
+pub struct Bolt {
+    position: Point,
+    previous_position: Point,
+}
+
+impl<'a> From<&'a Bolt> for Rect {
+    fn from(bolt: &'a Bolt) -> Rect {
+        Rect::new(bolt.position, BOLT_WIDTH, BOLT_HEIGHT)
+    }
+}
+
+impl Bolt {
+    pub fn new(position: Point) -> Bolt {
+        Bolt {
+            position,
+            previous_position: position,
+        }
+    }
+
+    pub fn shape(&self) -> Rect {
+        self.into()
+    }
+
+    pub fn position(&self) -> Point {
+        self.position
+    }
+}
+
+impl<T> Collide<T> for Bolt
+where
+    Rect: Collide<T>,
+{
+    fn collide(&self, other: &T) -> Option<Collision> {
+        self.shape().collide(other)
+    }
+}
+
+impl Updatable for Bolt {
+    fn update(&mut self, dt: f64) {
+        self.previous_position = self.position;
+        self.position.y -= BOLT_SPEED * dt;
+    }
+}
+
+#[cfg(feature = "render")]
+impl<T> Renderable<T> for Bolt
+where
+    T: RenderTarget,
+{
+    fn render(
+        &self,
+        canvas: &mut Canvas<T>,
+        context: &RenderContext,
+        texture: &Texture,
+    ) -> Result<(), Error> {
+        let copy_rects = TextureMaker::rocket(
+            RocketSprite::Yellow,
+            SDLRect::from_center(
+                context.translate_point(context.interpolate(self.previous_position, self.position)),
+                context.scale(BOLT_WIDTH),
+                context.scale(BOLT_HEIGHT),
+            ),
+        );
+        canvas
+            .copy(texture, copy_rects.src, copy_rects.dst)
+            .map_err(err_msg)?;
+        Ok(())
+    }
+}
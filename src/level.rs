@@ -1,19 +1,119 @@
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
 use std::fs::File;
 use std::io::Read;
+use std::path::Path;
 
+use ball::{Speed, BALL_RADIUS};
 use brick::*;
 use failure::{err_msg, Error};
+use player::Deflection;
+use state::BALL_OFFSET;
+use traits::Collide;
 use utils::{Pixels, Point};
 use wall::WALL_THICKNESS;
 
-#[derive(Deserialize, Serialize)]
+/// Line between the header and the bricks in the grid format
+const GRID_SEPARATOR: &str = "---";
+
+/// Room kept free of bricks at the bottom of a level, for the paddle and the
+/// ball at launch
+const PADDLE_LANE: Pixels = BALL_OFFSET + BALL_RADIUS;
+
+/// Problem found in a level, with the index of the bricks involved
+#[derive(Debug, Clone, PartialEq)]
+pub enum LevelError {
+    Overlap(usize, usize),
+    OutOfBounds(usize),
+    PaddleLane(usize),
+    /// The level would be won right away
+    NoBreakable,
+    /// Paddle deflection angles allowing flat trajectories
+    Deflection,
+    /// Ball speeds that would stop or slow down the ball
+    Speed,
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LevelError::Overlap(a, b) => write!(f, "bricks {} and {} overlap", a, b),
+            LevelError::OutOfBounds(i) => write!(f, "brick {} is out of the level", i),
+            LevelError::PaddleLane(i) => write!(f, "brick {} is in the paddle lane", i),
+            LevelError::NoBreakable => write!(f, "no breakable bricks"),
+            LevelError::Deflection => write!(
+                f,
+                "deflection angles must be 0 < min_angle <= max_angle < 90"
+            ),
+            LevelError::Speed => write!(f, "speed must be 0 < base <= max and increment >= 0"),
+        }
+    }
+}
+
+/// Every problem found in a level
+#[derive(Debug)]
+pub struct InvalidLevel {
+    pub errors: Vec<LevelError>,
+}
+
+impl fmt::Display for InvalidLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let errors: Vec<String> = self.errors.iter().map(|e| e.to_string()).collect();
+        write!(f, "Invalid level: {}", errors.join(", "))
+    }
+}
+
+impl error::Error for InvalidLevel {}
+
+#[derive(Clone, Deserialize, Serialize)]
 pub struct Level {
+    /// Shown in the HUD. Defaults to the name of the file.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    name: String,
     pub bricks: Vec<Brick>,
     height: Pixels,
     width: Pixels,
+    /// Points for each brick type, overriding `BrickType::points`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    points: HashMap<BrickType, u32>,
+    #[serde(default)]
+    deflection: Deflection,
+    #[serde(default)]
+    speed: Speed,
+    /// Make balls bounce off each other
+    #[serde(default)]
+    ball_collisions: bool,
+    /// Position in the campaign, starting from 1
+    #[serde(skip)]
+    number: u32,
 }
 
 impl Level {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn deflection(&self) -> Deflection {
+        self.deflection
+    }
+
+    pub fn speed(&self) -> Speed {
+        self.speed
+    }
+
+    pub fn ball_collisions(&self) -> bool {
+        self.ball_collisions
+    }
+
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    pub fn set_number(&mut self, number: u32) {
+        self.number = number;
+    }
+
     pub fn height(&self) -> u32 {
         self.height as u32
     }
@@ -22,18 +122,197 @@ impl Level {
         self.width as u32
     }
 
+    /// Points earned by destroying a brick of the given type in this level
+    pub fn points(&self, brick_type: BrickType) -> u32 {
+        self.points
+            .get(&brick_type)
+            .cloned()
+            .unwrap_or_else(|| brick_type.points())
+    }
+
+    /// Check the bricks fit in the level, leave the paddle lane free, don't
+    /// overlap, and that at least one of them can be broken
+    pub fn validate(&self) -> Result<(), InvalidLevel> {
+        let mut errors = Vec::new();
+        for (i, brick) in self.bricks.iter().enumerate() {
+            let (half_width, half_height) = (brick.width() / 2., brick.height() / 2.);
+            // Inside the left, top and right walls
+            if brick.center.x - half_width < WALL_THICKNESS
+                || brick.center.x + half_width > self.width - WALL_THICKNESS
+                || brick.center.y - half_height < WALL_THICKNESS
+                || brick.center.y + half_height > self.height
+            {
+                errors.push(LevelError::OutOfBounds(i));
+            } else if brick.center.y + half_height > self.height - PADDLE_LANE {
+                errors.push(LevelError::PaddleLane(i));
+            }
+
+            for (j, other) in self.bricks.iter().enumerate().skip(i + 1) {
+                if brick.shape().collide(&other.shape()).is_some() {
+                    errors.push(LevelError::Overlap(i, j));
+                }
+            }
+        }
+
+        if !self.bricks.iter().any(|b| b.breakable && b.alive()) {
+            errors.push(LevelError::NoBreakable);
+        }
+
+        let deflection = self.deflection;
+        if !(deflection.min_angle > 0.
+            && deflection.min_angle <= deflection.max_angle
+            && deflection.max_angle < 90.)
+        {
+            errors.push(LevelError::Deflection);
+        }
+
+        let speed = self.speed;
+        if !(speed.base > 0. && speed.base <= speed.max && speed.increment >= 0.) {
+            errors.push(LevelError::Speed);
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(InvalidLevel { errors })
+        }
+    }
+
     fn load(body: &str) -> Result<Self, Error> {
         serde_json::from_str(&body).map_err(err_msg)
     }
 
+    /// Read the grid format: a `key: value` header giving the `name`, the
+    /// `brick` size and the `padding` between bricks (as `<x>x<y>`), and
+    /// optionally the level `height`, then a `---` line and one character per
+    /// brick: `.` for none, `S` for simple, `H` for hard, `X` for super, `A`
+    /// for silver and `G` for gold.
+    /// Lines starting with `#` are ignored.
+    fn load_grid(body: &str) -> Result<Self, Error> {
+        let mut lines = body
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim_end()))
+            .filter(|(_, line)| !line.starts_with('#'));
+
+        let mut name = String::new();
+        let (mut brick_width, mut brick_height) = (32., 16.);
+        let (mut pad_x, mut pad_y) = (2., 2.);
+        let mut height = None;
+        for (n, line) in lines.by_ref() {
+            if line == GRID_SEPARATOR {
+                break;
+            }
+            if line.is_empty() {
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once(':')
+                .ok_or_else(|| err_msg(format!("line {}: expected `key: value`", n)))?;
+            let value = value.trim();
+            match key.trim() {
+                "name" => name = value.to_string(),
+                "brick" => {
+                    let (w, h) = grid_size(n, value)?;
+                    brick_width = w;
+                    brick_height = h;
+                }
+                "padding" => {
+                    let (x, y) = grid_size(n, value)?;
+                    pad_x = x;
+                    pad_y = y;
+                }
+                "height" => height = Some(grid_number(n, value)?),
+                key => return Err(err_msg(format!("line {}: unknown key `{}`", n, key))),
+            }
+        }
+
+        let offset = Point::new(
+            brick_width / 2. + pad_x + WALL_THICKNESS,
+            brick_height / 2. + pad_y + WALL_THICKNESS,
+        );
+        let mut bricks = Vec::new();
+        let (mut columns, mut rows) = (0, 0);
+        for (row, (n, line)) in lines.enumerate() {
+            for (column, c) in line.chars().enumerate() {
+                let brick_type = match c {
+                    '.' | ' ' => continue,
+                    'S' => BrickType::Simple,
+                    'H' => BrickType::Hard,
+                    'X' => BrickType::Super,
+                    'A' => BrickType::Silver,
+                    'G' => BrickType::Gold,
+                    c => return Err(err_msg(format!("line {}: unknown brick `{}`", n, c))),
+                };
+                let center = Point::new(
+                    column as Pixels * (brick_width + pad_x),
+                    row as Pixels * (brick_height + pad_y),
+                );
+                bricks.push(Brick::new(
+                    brick_type,
+                    center + offset,
+                    brick_width,
+                    brick_height,
+                ));
+            }
+            columns = columns.max(line.chars().count());
+            rows = row + 1;
+        }
+
+        let bricks_height = (brick_height + pad_y) * rows as Pixels + WALL_THICKNESS;
+        Ok(Level {
+            name,
+            bricks,
+            width: (brick_width + pad_x) * columns as Pixels + WALL_THICKNESS * 2. + pad_x,
+            height: height.unwrap_or(bricks_height * 3.),
+            points: HashMap::new(),
+            deflection: Deflection::default(),
+            speed: Speed::default(),
+            ball_collisions: false,
+            number: 0,
+        })
+    }
+
+    /// Load a level, in the grid format if the file ends with `.grid`, or in
+    /// JSON otherwise
     pub fn load_file(filename: &str) -> Result<Self, Error> {
         let mut file = File::open(filename)?;
         let mut contents = String::new();
         file.read_to_string(&mut contents)?;
-        Level::load(&contents)
+        let path = Path::new(filename);
+        let mut level = if path.extension().is_some_and(|e| e == "grid") {
+            Level::load_grid(&contents)?
+        } else {
+            Level::load(&contents)?
+        };
+        if level.name.is_empty() {
+            if let Some(stem) = path.file_stem() {
+                level.name = stem.to_string_lossy().into_owned();
+            }
+        }
+        level.validate()?;
+        Ok(level)
     }
 }
 
+fn grid_number(line: usize, value: &str) -> Result<Pixels, Error> {
+    value
+        .parse()
+        .map_err(|_| err_msg(format!("line {}: expected a number, got `{}`", line, value)))
+}
+
+/// Parse a `<x>x<y>` size from the grid header
+fn grid_size(line: usize, value: &str) -> Result<(Pixels, Pixels), Error> {
+    let (x, y) = value.split_once('x').ok_or_else(|| {
+        err_msg(format!(
+            "line {}: expected `<x>x<y>`, got `{}`",
+            line, value
+        ))
+    })?;
+    Ok((grid_number(line, x.trim())?, grid_number(line, y.trim())?))
+}
+
 impl Default for Level {
     fn default() -> Self {
         const BRICK_WIDTH: Pixels = 32.0;
@@ -57,7 +336,11 @@ impl Default for Level {
                 };
 
                 bricks.push(Brick::new(
-                    if i % 2 == 0 {
+                    if j == 0 {
+                        BrickType::Silver
+                    } else if j == M / 2 && (i == 0 || i == N - 1) {
+                        BrickType::Gold
+                    } else if i % 2 == 0 {
                         BrickType::Simple
                     } else {
                         BrickType::Hard
@@ -70,9 +353,118 @@ impl Default for Level {
         }
 
         Level {
+            name: String::from("default"),
             bricks,
             width: (BRICK_WIDTH + BRICK_V_PAD) * N as f64 + WALL_THICKNESS * 2. + BRICK_V_PAD,
             height: ((BRICK_HEIGHT + BRICK_H_PAD) * M as f64 + WALL_THICKNESS) * 3.,
+            points: HashMap::new(),
+            deflection: Deflection::default(),
+            speed: Speed::default(),
+            ball_collisions: false,
+            number: 1,
         }
     }
 }
+
+#[cfg(test)]
+mod level_test {
+    use super::*;
+
+    #[test]
+    fn test_points() {
+        let level =
+            Level::load(r#"{"bricks": [], "width": 100, "height": 100, "points": {"hard": 250}}"#)
+                .unwrap();
+        assert_eq!(level.points(BrickType::Hard), 250);
+        assert_eq!(level.points(BrickType::Simple), BrickType::Simple.points());
+    }
+
+    #[test]
+    fn test_grid() {
+        let level = Level::load_grid(
+            "# A small level\nname: tiny\nbrick: 20x10\npadding: 2x4\n---\nS.H\n.X\n",
+        )
+        .unwrap();
+        assert_eq!(level.name(), "tiny");
+        assert_eq!(level.width(), 3 * 22 + 2 + 2 * WALL_THICKNESS as u32);
+        let types: Vec<BrickType> = level.bricks.iter().map(|b| b.brick_type()).collect();
+        assert_eq!(
+            types,
+            vec![BrickType::Simple, BrickType::Hard, BrickType::Super]
+        );
+        assert_eq!(level.bricks[2].center.x, level.bricks[0].center.x + 22.);
+        assert_eq!(level.bricks[2].center.y, level.bricks[0].center.y + 14.);
+
+        assert!(Level::load_grid("---\nSQ\n").is_err());
+        assert!(Level::load_grid("brick: 20\n---\nS\n").is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Level::default().validate().is_ok());
+
+        let mut level = Level::default();
+        let brick = |brick_type, x, y| Brick::new(brick_type, Point::new(x, y), 32., 16.);
+        level.bricks = vec![
+            brick(BrickType::Super, 100., 100.),
+            brick(BrickType::Super, 110., 105.),
+            brick(BrickType::Super, -10., 100.),
+            brick(BrickType::Super, 100., level.height - 20.),
+            brick(BrickType::Super, level.width - 17., 100.),
+            brick(BrickType::Super, 200., 9.),
+        ];
+        level.deflection.max_angle = 90.;
+        assert_eq!(
+            level.validate().unwrap_err().errors,
+            vec![
+                LevelError::Overlap(0, 1),
+                LevelError::OutOfBounds(2),
+                LevelError::PaddleLane(3),
+                LevelError::OutOfBounds(4),
+                LevelError::OutOfBounds(5),
+                LevelError::NoBreakable,
+                LevelError::Deflection,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_deflection() {
+        let deflection = |min_angle, max_angle| {
+            let level = Level {
+                deflection: Deflection {
+                    min_angle,
+                    max_angle,
+                    ..Deflection::default()
+                },
+                ..Level::default()
+            };
+            level.validate().is_ok()
+        };
+        assert!(deflection(15., 60.));
+        assert!(deflection(30., 30.));
+        assert!(!deflection(0., 60.));
+        assert!(!deflection(15., 90.));
+        assert!(!deflection(60., 15.));
+    }
+
+    #[test]
+    fn test_validate_speed() {
+        let speed = |base, increment, max| {
+            let level = Level {
+                speed: Speed {
+                    base,
+                    increment,
+                    max,
+                },
+                ..Level::default()
+            };
+            level.validate().is_ok()
+        };
+        assert!(speed(400., 4., 640.));
+        assert!(speed(400., 0., 400.));
+        assert!(!speed(0., 4., 640.));
+        assert!(!speed(400., -4., 640.));
+        assert!(!speed(640., 4., 400.));
+    }
+}
@@ -0,0 +1,40 @@
+extern crate failure;
+extern crate rand;
+extern crate rand_pcg;
+#[cfg(feature = "render")]
+extern crate sdl2;
+
+#[macro_use]
+extern crate serde_derive;
+
+extern crate serde;
+extern crate serde_json;
+
+pub mod ball;
+pub mod bonus;
+pub mod brick;
+pub mod broadphase;
+pub mod campaign;
+pub mod effect;
+#[cfg(feature = "render")]
+pub mod hud;
+pub mod laser;
+pub mod level;
+pub mod player;
+pub mod replay;
+pub mod resize;
+#[cfg(feature = "render")]
+pub mod scene;
+#[cfg(feature = "render")]
+pub mod screen;
+pub mod shape;
+pub mod state;
+#[cfg(feature = "render")]
+pub mod text;
+#[cfg(feature = "render")]
+pub mod textures;
+pub mod timestep;
+pub mod traits;
+pub mod utils;
+pub mod wall;
+pub mod watch;
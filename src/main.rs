@@ -1,39 +1,26 @@
+extern crate arkanoid;
 extern crate failure;
-extern crate rand;
 extern crate sdl2;
 
-#[macro_use]
-extern crate serde_derive;
-
-extern crate serde;
-extern crate serde_json;
-
 use failure::{err_msg, Error};
 use sdl2::event::{Event, WindowEvent};
-use sdl2::keyboard::{KeyboardState, Keycode, Scancode};
+use sdl2::keyboard::{KeyboardState, Scancode};
 use sdl2::pixels::Color;
 use sdl2::render::Canvas;
 use sdl2::video::Window;
 use sdl2::{EventPump, Sdl};
+use std::env;
+use std::process;
 use std::time::Instant;
 
-mod ball;
-mod bonus;
-mod brick;
-mod level;
-mod player;
-mod resize;
-mod shape;
-mod state;
-mod textures;
-mod traits;
-mod utils;
-mod wall;
-
-use level::Level;
-use resize::{RenderContext, Size};
-use state::State;
-use traits::*;
+use arkanoid::campaign::{validate_levels, Campaign};
+use arkanoid::hud::HUD_HEIGHT;
+use arkanoid::level::Level;
+use arkanoid::replay::Replay;
+use arkanoid::resize::{RenderContext, Size};
+use arkanoid::scene::App;
+use arkanoid::state::Input;
+use arkanoid::traits::*;
 
 fn init(width: u32, height: u32) -> Result<(Sdl, Canvas<Window>, EventPump), Error> {
     let sdl_context = sdl2::init().map_err(err_msg)?;
@@ -55,16 +42,88 @@ fn init(width: u32, height: u32) -> Result<(Sdl, Canvas<Window>, EventPump), Err
     Ok((sdl_context, canvas, event_pump))
 }
 
+/// Render context fitting `level` and the HUD above it in the window
+fn level_context(level: &Level, canvas: &Canvas<Window>) -> RenderContext {
+    let size = Size::new(canvas.window().drawable_size());
+    let mut context = RenderContext::new(Size::new((level.width(), level.height())), size);
+    context.reserve_top(HUD_HEIGHT);
+    context.fit(size);
+    context
+}
+
+/// Value following `name` on the command line, if any
+fn arg(args: &[String], name: &str) -> Option<String> {
+    args.iter()
+        .position(|a| a == name)
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+}
+
+/// Check every level file in `dir`, and tell whether they are all valid
+fn check_levels(dir: &str) -> bool {
+    let results = validate_levels(dir).expect("Could not read levels directory");
+    let mut valid = true;
+    for (file, level) in results {
+        match level {
+            Ok(_) => println!("{}: ok", file),
+            Err(e) => {
+                println!("{}: {}", file, e);
+                valid = false;
+            }
+        }
+    }
+    valid
+}
+
 fn main() {
     // println!("{}", serde_json::to_string(&Level::default()).unwrap());
 
-    let level = Level::load_file("levels/default.json").expect("Could not load level file");
-    let (_sdl_context, mut canvas, mut event_pump) = init(level.height(), level.width()).unwrap();
-    let mut context = RenderContext::new(
-        Size::new((level.width(), level.height())),
-        Size::new(canvas.window().drawable_size()),
-    );
-    let mut state = State::new(level);
+    let args: Vec<String> = env::args().collect();
+    if args.get(1).map(String::as_str) == Some("validate-levels") {
+        let dir = args.get(2).map_or("levels", String::as_str);
+        process::exit(if check_levels(dir) { 0 } else { 1 });
+    }
+    let watch = args.iter().any(|a| a == "--watch" || a == "--watch-bricks");
+    if watch && arg(&args, "--replay").is_some() {
+        eprintln!("--watch and --watch-bricks cannot be used with --replay");
+        process::exit(1);
+    }
+    let mut app = match arg(&args, "--replay") {
+        Some(file) => {
+            let replay = Replay::load_file(&file).expect("Could not load replay file");
+            let campaign =
+                Campaign::load_file(&replay.campaign).expect("Could not load campaign file");
+            App::replay(replay, campaign)
+        }
+        None => {
+            let campaign_file =
+                arg(&args, "--campaign").unwrap_or_else(|| String::from("levels/campaign.json"));
+            let record_file = arg(&args, "--record").unwrap_or_else(|| String::from("replay.json"));
+            let campaign =
+                Campaign::load_file(&campaign_file).expect("Could not load campaign file");
+            let mut app = App::new(&campaign_file, campaign, &record_file);
+            // Replays carry this option themselves
+            if args.iter().any(|a| a == "--ball-collisions") {
+                app.force_ball_collisions();
+            }
+            app
+        }
+    };
+    if args.iter().any(|a| a == "--watch") {
+        app.watch(false);
+    } else if args.iter().any(|a| a == "--watch-bricks") {
+        app.watch(true);
+    }
+
+    let (_sdl_context, mut canvas, mut event_pump) = init(
+        app.level().height() + HUD_HEIGHT as u32,
+        app.level().width(),
+    )
+    .unwrap();
+    let mut context = level_context(app.level(), &canvas);
+    // Level being shown and its size, which can change on reload
+    let layout = |app: &App| (app.current(), app.level().width(), app.level().height());
+    let mut current_layout = layout(&app);
 
     let creator = canvas.texture_creator();
     let mut sprites =
@@ -74,51 +133,15 @@ fn main() {
 
     let mut last_update = Instant::now();
     'running: loop {
-        let alive = state.alive();
-        let won = state.won();
-
-        if !won && alive {
-            canvas.set_draw_color(Color::RGB(0, 0, 0));
-            canvas.clear();
-            state.render(&mut canvas, &context, &texture).unwrap();
-            canvas.present();
-
-            let now = Instant::now();
-            let dt = now.duration_since(last_update);
-            let dt: f64 = dt.as_secs() as f64 + dt.subsec_nanos() as f64 * 1e-9;
-            last_update = now;
-
-            let player_input = {
-                let keyboard_state = KeyboardState::new(&event_pump);
-                let mut input = 0;
-                if keyboard_state.is_scancode_pressed(Scancode::Left) {
-                    input -= 1;
-                }
-                if keyboard_state.is_scancode_pressed(Scancode::Right) {
-                    input += 1;
-                }
-                input as f64
-            };
-
-            state.input(player_input);
-            state.update(dt);
-        } else if won {
-            canvas.set_draw_color(Color::RGB(0, 200, 0));
-            canvas.clear();
-            canvas.present();
-        } else {
-            canvas.set_draw_color(Color::RGB(200, 0, 0));
-            canvas.clear();
-            canvas.present();
-        }
-
         for event in event_pump.poll_iter() {
             match event {
-                Event::Quit { .. }
-                | Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
+                Event::Quit { .. } => break 'running,
+
+                Event::KeyDown {
+                    keycode: Some(key),
+                    repeat: false,
                     ..
-                } => break 'running,
+                } if !app.key_down(key) => break 'running,
 
                 Event::Window {
                     win_event: WindowEvent::SizeChanged(_, _),
@@ -134,5 +157,37 @@ fn main() {
                 _ => {}
             }
         }
+
+        let now = Instant::now();
+        let dt = now.duration_since(last_update);
+        last_update = now;
+
+        let keyboard_state = KeyboardState::new(&event_pump);
+        let mut direction = 0;
+        if keyboard_state.is_scancode_pressed(Scancode::Left) {
+            direction -= 1;
+        }
+        if keyboard_state.is_scancode_pressed(Scancode::Right) {
+            direction += 1;
+        }
+        let input = Input {
+            direction: direction as f64,
+            fire: keyboard_state.is_scancode_pressed(Scancode::Space),
+            release: false,
+        };
+        app.update(dt, input).expect("Could not save replay file");
+
+        if layout(&app) != current_layout {
+            current_layout = layout(&app);
+            context = level_context(app.level(), &canvas);
+        }
+
+        canvas.set_draw_color(Color::RGB(0, 0, 0));
+        canvas.clear();
+        context.set_alpha(app.alpha());
+        app.render(&mut canvas, &context, &texture).unwrap();
+        canvas.present();
     }
+
+    app.save().expect("Could not save replay file");
 }
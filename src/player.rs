@@ -1,12 +1,19 @@
+#[cfg(feature = "render")]
 use failure::err_msg;
+#[cfg(feature = "render")]
 use resize::RenderContext;
+#[cfg(feature = "render")]
 use sdl2::rect::Rect as SDLRect;
+#[cfg(feature = "render")]
 use sdl2::render::{Canvas, RenderTarget, Texture};
 
 use shape::Rect;
+#[cfg(feature = "render")]
 use textures::{TextureMaker, VesselSprite};
-use traits::{Collision, Renderable, Updatable};
-use utils::{Pixels, Point, Vector};
+#[cfg(feature = "render")]
+use traits::Renderable;
+use traits::{Collision, Updatable};
+use utils::{Pixels, Point, Rad, PI};
 
 const PLAYER_INITIAL_WIDTH: Pixels = 80.0;
 const PLAYER_GROWTH: Pixels = 20.0;
@@ -14,12 +21,49 @@ const PLAYER_MAX_WIDTH: Pixels = 160.0;
 pub const PLAYER_THICKNESS: Pixels = 16.0;
 const PLAYER_FRICTION: f64 = 10.;
 const PLAYER_ACCELERATION: f64 = 5000.;
+/// How fast the paddle grows or shrinks, in pixels per second
+const PLAYER_RESIZE_SPEED: f64 = 200.;
+
+/// How the paddle sends the ball back, depending on where the ball lands on it
+/// and on how fast the paddle moves. Angles are in degrees from the vertical.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Deflection {
+    pub min_angle: f64,
+    pub max_angle: f64,
+    /// Degrees added for each pixel per second of paddle speed
+    pub spin: f64,
+}
+
+impl Deflection {
+    /// Heading of a ball leaving the paddle, `offset` being where it landed
+    /// from -1 (left end) to 1 (right end)
+    pub fn angle(&self, offset: f64, paddle_velocity: Pixels) -> Rad {
+        let angle = offset * self.max_angle + paddle_velocity * self.spin;
+        let clamped = angle.abs().max(self.min_angle).min(self.max_angle);
+        -PI / 2. + clamped.copysign(angle).to_radians()
+    }
+}
+
+impl Default for Deflection {
+    fn default() -> Self {
+        Deflection {
+            min_angle: 15.,
+            max_angle: 60.,
+            spin: 0.01,
+        }
+    }
+}
 
 pub struct Player {
     position: Point,
+    previous_position: Point,
     velocity: Pixels,
     acceleration: Pixels,
     width: Pixels,
+    previous_width: Pixels,
+    /// Width the paddle is growing or shrinking to
+    target_width: Pixels,
 }
 
 impl Into<Rect> for &Player {
@@ -32,14 +76,21 @@ impl Player {
     pub fn new(position: Point) -> Player {
         Player {
             position,
+            previous_position: position,
             velocity: 0.,
             acceleration: 0.,
             width: PLAYER_INITIAL_WIDTH,
+            previous_width: PLAYER_INITIAL_WIDTH,
+            target_width: PLAYER_INITIAL_WIDTH,
         }
     }
 
-    pub fn grow(&mut self) {
-        self.width = f64::min(self.width + PLAYER_GROWTH, PLAYER_MAX_WIDTH);
+    /// Grow the paddle once for each of the `count` Expand bonuses running
+    pub fn expand(&mut self, count: usize) {
+        self.target_width = f64::min(
+            PLAYER_INITIAL_WIDTH + PLAYER_GROWTH * count as f64,
+            PLAYER_MAX_WIDTH,
+        );
     }
 
     pub fn input(&mut self, input: f64) {
@@ -48,23 +99,44 @@ impl Player {
 
     pub fn bounce(&mut self, (normal, depth): Collision) {
         self.velocity = -self.velocity;
-        self.position.x = self.position.x
-            + Vector {
-                angle: normal,
-                norm: depth,
-            }
-            .x();
+        self.position.x += normal.x * depth;
     }
 
     pub fn position(&self) -> Point {
         self.position
     }
 
+    pub fn width(&self) -> Pixels {
+        self.width
+    }
+
+    pub fn velocity(&self) -> Pixels {
+        self.velocity
+    }
+
     pub fn shape(&self) -> Rect {
         self.into()
     }
+
+    /// Vessel sprite closest in size to a paddle `width` wide
+    #[cfg(feature = "render")]
+    fn sprite(width: Pixels) -> VesselSprite {
+        use textures::VesselSprite::*;
+        let growth = (width - PLAYER_INITIAL_WIDTH) / (PLAYER_MAX_WIDTH - PLAYER_INITIAL_WIDTH);
+        match (growth * 7.).round() as i32 {
+            i32::MIN..=0 => Size1,
+            1 => Size2,
+            2 => Size3,
+            3 => Size4,
+            4 => Size5,
+            5 => Size6,
+            6 => Size7,
+            _ => Size8,
+        }
+    }
 }
 
+#[cfg(feature = "render")]
 impl<T> Renderable<T> for Player
 where
     T: RenderTarget,
@@ -82,11 +154,12 @@ where
         //         context.scale(PLAYER_THICKNESS),
         //     ))
         //     .map_err(err_msg)?;
+        let width = context.interpolate_size(self.previous_width, self.width);
         let copy_rects = TextureMaker::vessel(
-            VesselSprite::Size1,
+            Player::sprite(width),
             SDLRect::from_center(
-                context.translate_point(self.position),
-                context.scale(self.width),
+                context.translate_point(context.interpolate(self.previous_position, self.position)),
+                context.scale(width),
                 context.scale(PLAYER_THICKNESS),
             ),
         );
@@ -99,6 +172,7 @@ where
 
 impl Updatable for Player {
     fn update(&mut self, dt: f64) {
+        self.previous_position = self.position;
         let acceleration =
             (self.acceleration * PLAYER_ACCELERATION) - (self.velocity * PLAYER_FRICTION);
         self.velocity += acceleration * dt;
@@ -108,5 +182,33 @@ impl Updatable for Player {
         }
 
         self.position.x += self.velocity * dt;
+
+        self.previous_width = self.width;
+        let resize = PLAYER_RESIZE_SPEED * dt;
+        self.width = (self.target_width - self.width).max(-resize).min(resize) + self.width;
+    }
+}
+
+#[cfg(test)]
+mod player_test {
+    use super::*;
+
+    #[test]
+    fn test_deflection() {
+        let deflection = Deflection::default();
+        let from_vertical = |offset, velocity| {
+            (deflection.angle(offset, velocity) + PI / 2.)
+                .to_degrees()
+                .round()
+        };
+
+        assert_eq!(from_vertical(0.5, 0.), 30.);
+        assert_eq!(from_vertical(-1., 0.), -60.);
+        // Never too steep, nor too flat
+        assert_eq!(from_vertical(0.1, 0.), 15.);
+        assert_eq!(from_vertical(-0.1, 0.), -15.);
+        assert_eq!(from_vertical(1., 1000.), 60.);
+        // Moving the paddle puts some spin on the ball
+        assert_eq!(from_vertical(0.5, 500.), 35.);
     }
 }
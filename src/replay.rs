@@ -0,0 +1,151 @@
+use std::fs::File;
+use std::io::{Read, Write};
+use std::time::Duration;
+
+use failure::{err_msg, Error};
+
+use campaign::{Campaign, Game};
+use state::Input;
+use timestep::FixedStep;
+
+/// Bumped every time the file format or the simulation changes in a way
+/// older replays can't be played back with, including changes to the odds of
+/// random events
+pub const REPLAY_VERSION: u32 = 1;
+
+/// One frame of a recorded game
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct Frame {
+    /// Value given to `State::input`
+    pub input: Input,
+    /// Wall-clock time of the frame, in seconds
+    pub dt: f64,
+}
+
+impl Frame {
+    pub fn new(input: Input, dt: Duration) -> Self {
+        Frame {
+            input,
+            dt: dt.as_secs() as f64 + f64::from(dt.subsec_nanos()) * 1e-9,
+        }
+    }
+
+    /// Frame time as it was measured. Frames are shorter than a second, so
+    /// rounding to the nanosecond gives back the exact recorded duration.
+    pub fn duration(&self) -> Duration {
+        Duration::from_nanos((self.dt * 1e9).round() as u64)
+    }
+}
+
+/// Everything needed to play a game back exactly: the campaign, the RNG seed
+/// and the input of every frame
+#[derive(Deserialize, Serialize)]
+pub struct Replay {
+    version: u32,
+    pub campaign: String,
+    pub seed: u64,
+    /// Whether ball collisions were forced on for the game
+    #[serde(default)]
+    pub ball_collisions: bool,
+    pub frames: Vec<Frame>,
+}
+
+impl Replay {
+    pub fn new(campaign: &str, seed: u64) -> Self {
+        Replay {
+            version: REPLAY_VERSION,
+            campaign: campaign.to_string(),
+            seed,
+            ball_collisions: false,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, input: Input, dt: Duration) {
+        self.frames.push(Frame::new(input, dt));
+    }
+
+    fn load(body: &str) -> Result<Self, Error> {
+        let replay: Replay = serde_json::from_str(body).map_err(err_msg)?;
+        if replay.version != REPLAY_VERSION {
+            return Err(err_msg(format!(
+                "Unsupported replay version {} (expected {})",
+                replay.version, REPLAY_VERSION
+            )));
+        }
+        Ok(replay)
+    }
+
+    pub fn load_file(filename: &str) -> Result<Self, Error> {
+        let mut file = File::open(filename)?;
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+        Replay::load(&contents)
+    }
+
+    pub fn save_file(&self, filename: &str) -> Result<(), Error> {
+        let mut file = File::create(filename)?;
+        file.write_all(serde_json::to_string(self)?.as_bytes())?;
+        Ok(())
+    }
+
+    /// Play the whole game back without rendering it
+    pub fn run(&self, campaign: Campaign) -> Game {
+        let mut game = Game::new(campaign, self.seed);
+        game.force_ball_collisions(self.ball_collisions);
+        let mut stepper = FixedStep::default();
+        for frame in &self.frames {
+            if game.over() || game.victory() {
+                break;
+            }
+            game.next_level();
+            game.input(frame.input);
+            stepper.advance(frame.duration(), &mut game);
+        }
+        game
+    }
+}
+
+#[cfg(test)]
+mod replay_test {
+    use super::*;
+    use level::Level;
+    use state::{Input, State};
+
+    #[test]
+    fn test_replay() {
+        let mut replay = Replay::new("default", 1234);
+        let mut state = State::new(Level::default(), replay.seed);
+        let mut stepper = FixedStep::default();
+
+        // Wiggle around with an uneven frame rate
+        for i in 0..1500u32 {
+            if !state.alive() || state.won() {
+                break;
+            }
+            let input = Input {
+                direction: f64::from(i / 90 % 3) - 1.,
+                fire: i % 100 < 50,
+                release: i % 200 == 0,
+            };
+            let dt = Duration::from_micros(u64::from(4000 + i * 7919 % 30000));
+            replay.record(input, dt);
+            state.input(input);
+            stepper.advance(dt, &mut state);
+        }
+
+        let json = serde_json::to_string(&replay).unwrap();
+        let played = Replay::load(&json)
+            .unwrap()
+            .run(Campaign::single(Level::default()));
+
+        assert_eq!(played.state().bricks().len(), state.bricks().len());
+        assert_eq!(played.state().lives(), state.lives());
+    }
+
+    #[test]
+    fn test_replay_version() {
+        let json = r#"{"version": 2, "campaign": "", "seed": 0, "frames": []}"#;
+        assert!(Replay::load(json).is_err());
+    }
+}
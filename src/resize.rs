@@ -19,6 +19,8 @@ pub struct RenderContext {
     scale: f64,
     offset: Size,
     base: Size,
+    alpha: f64,
+    top: Pixels,
 }
 
 impl RenderContext {
@@ -31,8 +33,15 @@ impl RenderContext {
                 height: 0,
             },
             base: base,
+            alpha: 1.,
+            top: 0.,
         }
     }
+    /// Reserve a band above the level. It is drawn at negative coordinates.
+    pub fn reserve_top(&mut self, height: Pixels) {
+        self.base.height += (height - self.top) as i32;
+        self.top = height;
+    }
     pub fn fit(&mut self, size: Size) {
         let scale = Self::min_scale(self.base, size);
         self.scale = scale as f64;
@@ -55,10 +64,26 @@ impl RenderContext {
     pub fn scale(&self, size: Pixels) -> u32 {
         (size * self.scale) as u32
     }
+    /// Set how far the frame being drawn is between the last two simulation
+    /// steps
+    pub fn set_alpha(&mut self, alpha: f64) {
+        self.alpha = alpha;
+    }
+    /// Blend a position between the last two simulation steps
+    pub fn interpolate(&self, previous: Point, current: Point) -> Point {
+        Point {
+            x: previous.x + (current.x - previous.x) * self.alpha,
+            y: previous.y + (current.y - previous.y) * self.alpha,
+        }
+    }
+    /// Blend a size between the last two simulation steps
+    pub fn interpolate_size(&self, previous: Pixels, current: Pixels) -> Pixels {
+        previous + (current - previous) * self.alpha
+    }
     pub fn translate_point(&self, position: Point) -> Point {
         Point {
             x: self.scale * position.x + self.offset.width as f64,
-            y: self.scale * position.y + self.offset.height as f64,
+            y: self.scale * (position.y + self.top) + self.offset.height as f64,
         }
     }
 }
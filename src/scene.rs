@@ -0,0 +1,398 @@
+use failure::Error;
+use rand;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+use sdl2::render::{Canvas, RenderTarget, Texture};
+use std::time::Duration;
+use std::vec;
+
+use campaign::{Campaign, Game};
+use hud::Hud;
+use level::Level;
+use replay::{Frame, Replay};
+use resize::RenderContext;
+use screen::{Backdrop, Screen};
+use state::Input;
+use textures::BackgroundSprite;
+use timestep::FixedStep;
+use traits::Renderable;
+use watch::LevelWatcher;
+
+/// Time spent on the "level cleared" overlay before moving on, in seconds
+const LEVEL_CLEARED_DELAY: f64 = 2.;
+/// Characters per line of the reload error overlay
+const ERROR_LINE_LENGTH: usize = 48;
+
+/// What the application is currently showing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scene {
+    Title,
+    Playing,
+    Paused,
+    /// Seconds left before the next level starts
+    LevelCleared(f64),
+    GameOver,
+    Victory,
+}
+
+/// The game wrapped in its scenes. Each scene handles its own keys, updates
+/// and rendering.
+pub struct App {
+    scene: Scene,
+    game: Game,
+    stepper: FixedStep,
+    campaign_file: String,
+    /// Game being recorded, until it is saved
+    recording: Option<Replay>,
+    record_file: Option<String>,
+    playback: Option<vec::IntoIter<Frame>>,
+    /// Watches the file of the current level, in dev mode
+    watcher: Option<LevelWatcher>,
+    /// Only patch the bricks in when the level file changes
+    bricks_only: bool,
+    /// Why the level file could not be reloaded
+    reload_error: Option<String>,
+    /// Release the balls on the next update, after the key was pressed
+    release: bool,
+    /// The fire key changed the scene: ignore it until it is let go, so that
+    /// it does not shoot in the game that comes next
+    fire_consumed: bool,
+}
+
+impl App {
+    /// Start on the title screen, recording each game to `record_file`
+    pub fn new(campaign_file: &str, campaign: Campaign, record_file: &str) -> Self {
+        App {
+            scene: Scene::Title,
+            game: Game::new(campaign, 0),
+            stepper: FixedStep::default(),
+            campaign_file: campaign_file.to_string(),
+            recording: None,
+            record_file: Some(record_file.to_string()),
+            playback: None,
+            watcher: None,
+            bricks_only: false,
+            reload_error: None,
+            release: false,
+            fire_consumed: false,
+        }
+    }
+
+    /// Play `replay` back right away. Restarting is disabled.
+    pub fn replay(replay: Replay, campaign: Campaign) -> Self {
+        let mut game = Game::new(campaign, replay.seed);
+        game.force_ball_collisions(replay.ball_collisions);
+        App {
+            scene: Scene::Playing,
+            game,
+            stepper: FixedStep::default(),
+            campaign_file: replay.campaign.clone(),
+            recording: None,
+            record_file: None,
+            playback: Some(replay.frames.into_iter()),
+            watcher: None,
+            bricks_only: false,
+            reload_error: None,
+            release: false,
+            fire_consumed: false,
+        }
+    }
+
+    /// Make balls bounce off each other on every level
+    pub fn force_ball_collisions(&mut self) {
+        self.game.force_ball_collisions(true);
+    }
+
+    /// Dev mode: reload the current level whenever its file changes, either
+    /// from scratch or by patching in only its bricks
+    pub fn watch(&mut self, bricks_only: bool) {
+        self.bricks_only = bricks_only;
+        self.watcher = self
+            .game
+            .campaign()
+            .file(self.game.current())
+            .map(LevelWatcher::new);
+    }
+
+    pub fn scene(&self) -> Scene {
+        self.scene
+    }
+
+    pub fn level(&self) -> &Level {
+        self.game.level()
+    }
+
+    /// Index of the level being played
+    pub fn current(&self) -> usize {
+        self.game.current()
+    }
+
+    /// Interpolation factor between the last two simulation steps
+    pub fn alpha(&self) -> f64 {
+        self.stepper.alpha()
+    }
+
+    fn start(&mut self) {
+        let seed = rand::random();
+        self.game.restart(seed);
+        self.stepper = FixedStep::default();
+        let mut recording = Replay::new(&self.campaign_file, seed);
+        recording.ball_collisions = self.game.ball_collisions_forced();
+        self.recording = Some(recording);
+        self.scene = Scene::Playing;
+    }
+
+    /// Write the recording of the current game, if any, to the record file
+    pub fn save(&mut self) -> Result<(), Error> {
+        if let (Some(recording), Some(file)) = (self.recording.take(), &self.record_file) {
+            recording.save_file(file)?;
+        }
+        Ok(())
+    }
+
+    /// Handle a key press. Returns `false` when the application should quit.
+    pub fn key_down(&mut self, key: Keycode) -> bool {
+        let playback = self.playback.is_some();
+        let scene = self.scene;
+        match (self.scene, key) {
+            (Scene::Title, Keycode::Space) | (Scene::Title, Keycode::Return) => self.start(),
+            (Scene::Title, Keycode::Escape) => return false,
+
+            (Scene::Playing, Keycode::Space) => self.release = true,
+            (Scene::Playing, Keycode::P) | (Scene::Playing, Keycode::Escape) => {
+                self.scene = Scene::Paused
+            }
+
+            (Scene::Paused, Keycode::P) | (Scene::Paused, Keycode::Space) => {
+                self.scene = Scene::Playing
+            }
+            (Scene::Paused, Keycode::Escape)
+            | (Scene::GameOver, Keycode::Escape)
+            | (Scene::Victory, Keycode::Escape) => {
+                if playback {
+                    return false;
+                }
+                self.scene = Scene::Title;
+            }
+
+            (Scene::LevelCleared(_), Keycode::Space) => self.next_level(),
+
+            (Scene::GameOver, Keycode::Space) | (Scene::Victory, Keycode::Space) if !playback => {
+                self.start()
+            }
+
+            _ => {}
+        }
+        if key == Keycode::Space && self.scene != scene {
+            self.fire_consumed = true;
+        }
+        true
+    }
+
+    fn next_level(&mut self) {
+        self.game.next_level();
+        self.scene = Scene::Playing;
+    }
+
+    /// Advance the current scene by `dt`, with `input` from the player. The
+    /// input is ignored when playing a replay back.
+    pub fn update(&mut self, dt: Duration, input: Input) -> Result<(), Error> {
+        self.reload();
+
+        match self.scene {
+            Scene::Playing => self.play(dt, input)?,
+            Scene::LevelCleared(remaining) => {
+                let remaining = remaining - duration_secs(dt);
+                if remaining > 0. {
+                    self.scene = Scene::LevelCleared(remaining);
+                } else {
+                    self.next_level();
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn reload(&mut self) {
+        let file = self.game.campaign().file(self.game.current());
+        let level = match self.watcher {
+            // Follow the game to its next level
+            Some(ref watcher) if Some(watcher.filename()) != file => {
+                self.watcher = file.map(LevelWatcher::new);
+                return;
+            }
+            Some(ref mut watcher) => watcher.poll(),
+            None => return,
+        };
+
+        match level {
+            Some(Ok(level)) => {
+                // The recording would not match the level file anymore
+                self.recording = None;
+                self.game.reload(level, self.bricks_only);
+                self.reload_error = None;
+                if !self.bricks_only && self.scene != Scene::Title {
+                    self.scene = Scene::Playing;
+                }
+            }
+            Some(Err(error)) => self.reload_error = Some(error.to_string()),
+            None => {}
+        }
+    }
+
+    fn play(&mut self, dt: Duration, mut input: Input) -> Result<(), Error> {
+        if self.fire_consumed {
+            self.fire_consumed = input.fire;
+            input.fire = false;
+        }
+
+        let frame = match self.playback {
+            // Play the recorded frames back, then freeze on the last one
+            Some(ref mut frames) => frames.next().map(|f| (f.input, f.duration())),
+            None => Some((
+                Input {
+                    release: self.release,
+                    ..input
+                },
+                dt,
+            )),
+        };
+        self.release = false;
+
+        if let Some((input, dt)) = frame {
+            if let Some(ref mut recording) = self.recording {
+                recording.record(input, dt);
+            }
+            self.game.input(input);
+            self.stepper.advance(dt, &mut self.game);
+        }
+
+        if self.game.over() {
+            self.scene = Scene::GameOver;
+            self.save()?;
+        } else if self.game.victory() {
+            self.scene = Scene::Victory;
+            self.save()?;
+        } else if self.game.level_cleared() {
+            self.scene = Scene::LevelCleared(LEVEL_CLEARED_DELAY);
+        }
+        Ok(())
+    }
+
+    fn render_game<T>(
+        &self,
+        canvas: &mut Canvas<T>,
+        context: &RenderContext,
+        texture: &Texture,
+    ) -> Result<(), Error>
+    where
+        T: RenderTarget,
+    {
+        self.game.state().render(canvas, context, texture)?;
+        Hud::new(self.game.state(), self.level().name(), self.level().width())
+            .render(canvas, context, texture)
+    }
+}
+
+/// Split `text` in lines of at most `length` characters, on spaces if possible
+fn wrap(text: &str, length: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.len() + 1 + word.len() > length {
+            lines.push(line);
+            line = String::new();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+fn duration_secs(d: Duration) -> f64 {
+    d.as_secs() as f64 + f64::from(d.subsec_nanos()) * 1e-9
+}
+
+impl<T> Renderable<T> for App
+where
+    T: RenderTarget,
+{
+    fn render(
+        &self,
+        canvas: &mut Canvas<T>,
+        context: &RenderContext,
+        texture: &Texture,
+    ) -> Result<(), Error> {
+        let white = Color::RGB(255, 255, 255);
+        let blue = Backdrop::Tiles(BackgroundSprite::Blue, BackgroundSprite::BlueDark);
+        let score = format!("Score {}", self.game.state().score());
+
+        let screen = match self.scene {
+            Scene::Title => Some(
+                Screen::new(self.level(), blue, "ARKANOID", Color::RGB(255, 220, 0))
+                    .line("Press Space to start")
+                    .line("Press Escape to quit"),
+            ),
+            Scene::Playing => None,
+            Scene::Paused => Some(
+                Screen::new(self.level(), Backdrop::Dim, "PAUSED", white)
+                    .line("Press P to resume")
+                    .line("Press Escape to leave"),
+            ),
+            Scene::LevelCleared(_) => Some(
+                Screen::new(self.level(), Backdrop::Dim, "LEVEL CLEARED", white)
+                    .line(self.level().name())
+                    .line(&score),
+            ),
+            Scene::GameOver => Some(
+                Screen::new(
+                    self.level(),
+                    Backdrop::Tiles(BackgroundSprite::Red, BackgroundSprite::RedDark),
+                    "GAME OVER",
+                    white,
+                )
+                .line(&score)
+                .line("Press Space to play again"),
+            ),
+            Scene::Victory => Some(
+                Screen::new(self.level(), blue, "VICTORY!", Color::RGB(255, 220, 0))
+                    .line(&format!(
+                        "All {} levels cleared",
+                        self.game.campaign().len()
+                    ))
+                    .line(&format!("{} lives left", self.game.state().lives()))
+                    .line(&score),
+            ),
+        };
+
+        match self.scene {
+            Scene::Playing | Scene::Paused | Scene::LevelCleared(_) => {
+                self.render_game(canvas, context, texture)?
+            }
+            _ => {}
+        }
+        if let Some(screen) = screen {
+            screen.render(canvas, context, texture)?;
+        }
+
+        if let Some(ref error) = self.reload_error {
+            let overlay = wrap(error, ERROR_LINE_LENGTH).iter().fold(
+                Screen::new(
+                    self.level(),
+                    Backdrop::Dim,
+                    "LEVEL ERROR",
+                    Color::RGB(255, 0, 0),
+                ),
+                |screen, line| screen.line(line),
+            );
+            overlay.render(canvas, context, texture)?;
+        }
+        Ok(())
+    }
+}
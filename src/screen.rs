@@ -0,0 +1,138 @@
+use failure::{err_msg, Error};
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Canvas, RenderTarget, Texture};
+
+use level::Level;
+use resize::RenderContext;
+use text::draw_text;
+use textures::{BackgroundSprite, TextureMaker};
+use traits::Renderable;
+use utils::{Pixels, Point};
+
+const TILE_SIZE: Pixels = 64.;
+const TITLE_SIZE: Pixels = 40.;
+const LINE_SIZE: Pixels = 16.;
+
+/// What is drawn behind the text of a screen
+pub enum Backdrop {
+    /// Checkerboard of two background tiles
+    Tiles(BackgroundSprite, BackgroundSprite),
+    /// Darken whatever was drawn before
+    Dim,
+}
+
+/// Full-screen message: a title and a few lines of text, over a backdrop
+pub struct Screen {
+    width: Pixels,
+    height: Pixels,
+    backdrop: Backdrop,
+    title: String,
+    color: Color,
+    lines: Vec<String>,
+}
+
+impl Screen {
+    pub fn new(level: &Level, backdrop: Backdrop, title: &str, color: Color) -> Self {
+        Screen {
+            width: f64::from(level.width()),
+            height: f64::from(level.height()),
+            backdrop,
+            title: title.to_string(),
+            color,
+            lines: Vec::new(),
+        }
+    }
+
+    pub fn line(mut self, line: &str) -> Self {
+        self.lines.push(line.to_string());
+        self
+    }
+
+    fn render_tiles<T>(
+        &self,
+        canvas: &mut Canvas<T>,
+        context: &RenderContext,
+        texture: &Texture,
+        light: BackgroundSprite,
+        dark: BackgroundSprite,
+    ) -> Result<(), Error>
+    where
+        T: RenderTarget,
+    {
+        let columns = (self.width / TILE_SIZE).ceil() as usize;
+        let rows = (self.height / TILE_SIZE).ceil() as usize;
+        for i in 0..columns {
+            for j in 0..rows {
+                let center = Point::new(
+                    (i as Pixels + 0.5) * TILE_SIZE,
+                    (j as Pixels + 0.5) * TILE_SIZE,
+                );
+                let sprite = if (i + j) % 2 == 0 { light } else { dark };
+                let copy_rects = TextureMaker::background(
+                    sprite,
+                    Rect::from_center(
+                        context.translate_point(center),
+                        context.scale(TILE_SIZE),
+                        context.scale(TILE_SIZE),
+                    ),
+                );
+                canvas
+                    .copy(texture, copy_rects.src, copy_rects.dst)
+                    .map_err(err_msg)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T> Renderable<T> for Screen
+where
+    T: RenderTarget,
+{
+    fn render(
+        &self,
+        canvas: &mut Canvas<T>,
+        context: &RenderContext,
+        texture: &Texture,
+    ) -> Result<(), Error> {
+        match self.backdrop {
+            Backdrop::Tiles(light, dark) => {
+                self.render_tiles(canvas, context, texture, light, dark)?
+            }
+            Backdrop::Dim => {
+                canvas.set_blend_mode(BlendMode::Blend);
+                canvas.set_draw_color(Color::RGBA(0, 0, 0, 160));
+                canvas
+                    .fill_rect(Rect::from_center(
+                        context.translate_point(Point::new(self.width / 2., self.height / 2.)),
+                        context.scale(self.width),
+                        context.scale(self.height),
+                    ))
+                    .map_err(err_msg)?;
+                canvas.set_blend_mode(BlendMode::None);
+            }
+        }
+
+        let center = Point::new(self.width / 2., self.height / 2.);
+        draw_text(
+            canvas,
+            context,
+            center + Point::new(0., -60.),
+            TITLE_SIZE,
+            &self.title,
+            self.color,
+        )?;
+        for (i, line) in self.lines.iter().enumerate() {
+            draw_text(
+                canvas,
+                context,
+                center + Point::new(0., 10. + i as Pixels * LINE_SIZE * 2.),
+                LINE_SIZE,
+                line,
+                Color::RGB(255, 255, 255),
+            )?;
+        }
+        Ok(())
+    }
+}
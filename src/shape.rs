@@ -1,10 +1,10 @@
-use traits::{Collide, Collision};
-use utils::{Pixels, Point, Rad, Vector, PI};
+use traits::{Collide, Collision, Impact, Sweep};
+use utils::{Pixels, Point, Vec2};
 
-const UP: Rad = -PI / 2.;
-const DOWN: Rad = PI / 2.;
-const LEFT: Rad = PI;
-const RIGHT: Rad = 0.;
+const UP: Vec2 = Vec2 { x: 0., y: -1. };
+const DOWN: Vec2 = Vec2 { x: 0., y: 1. };
+const LEFT: Vec2 = Vec2 { x: -1., y: 0. };
+const RIGHT: Vec2 = Vec2 { x: 1., y: 0. };
 
 #[derive(Debug)]
 pub struct Rect {
@@ -47,9 +47,32 @@ pub enum WallOrientation {
 pub struct InfiniteWall {
     pub orientation: WallOrientation,
     pub center: Point,
+    /// Opening in the wall, as a range of coordinates along it. Rectangles
+    /// entirely within that range go through, circles always bounce.
+    pub gap: Option<(Pixels, Pixels)>,
+}
+
+impl InfiniteWall {
+    /// Coordinate of a point along the wall
+    fn along(&self, point: Point) -> Pixels {
+        match self.orientation {
+            WallOrientation::Top | WallOrientation::Bottom => point.x,
+            WallOrientation::Left | WallOrientation::Right => point.y,
+        }
+    }
+
+    /// Check if something spanning from `from` to `to` along the wall fits
+    /// through the gap
+    fn through_gap(&self, from: Pixels, to: Pixels) -> bool {
+        match self.gap {
+            Some((start, end)) => from >= start && to <= end,
+            None => false,
+        }
+    }
 }
 
 impl Collide<Circle> for InfiniteWall {
+    /// The gap only lets the paddle through: balls bounce off the whole wall
     fn collide(&self, other: &Circle) -> Option<Collision> {
         match self.orientation {
             WallOrientation::Top if other.center.y - other.radius < self.center.y => Some((
@@ -74,6 +97,15 @@ impl Collide<Circle> for InfiniteWall {
 
 impl Collide<Rect> for InfiniteWall {
     fn collide(&self, other: &Rect) -> Option<Collision> {
+        let along = self.along(other.center);
+        let half = match self.orientation {
+            WallOrientation::Top | WallOrientation::Bottom => other.width / 2.,
+            WallOrientation::Left | WallOrientation::Right => other.height / 2.,
+        };
+        if self.through_gap(along - half, along + half) {
+            return None;
+        }
+
         match self.orientation {
             WallOrientation::Top if other.center.y - other.height / 2. < self.center.y => {
                 Some((DOWN, -(other.center.y - other.height / 2.) + self.center.y))
@@ -92,12 +124,27 @@ impl Collide<Rect> for InfiniteWall {
     }
 }
 
+impl Collide<Rect> for Rect {
+    fn collide(&self, other: &Rect) -> Option<Collision> {
+        // Grow self by the size of other and check for its center
+        Rect {
+            center: self.center,
+            width: self.width + other.width,
+            height: self.height + other.height,
+        }
+        .collide(&other.center)
+    }
+}
+
 impl Collide<Circle> for Circle {
     fn collide(&self, other: &Self) -> Option<Collision> {
-        let distance = Vector::from(self.center - other.center);
+        let distance = self.center - other.center;
 
-        if distance.norm < self.radius + other.radius {
-            Some((distance.angle, (self.radius + other.radius) - distance.norm))
+        if distance.norm() < self.radius + other.radius {
+            Some((
+                distance.normalize(),
+                (self.radius + other.radius) - distance.norm(),
+            ))
         } else {
             None
         }
@@ -106,10 +153,10 @@ impl Collide<Circle> for Circle {
 
 impl Collide<Point> for Circle {
     fn collide(&self, other: &Point) -> Option<Collision> {
-        let distance = Vector::from(self.center - *other);
+        let distance = self.center - *other;
 
-        if distance.norm < self.radius {
-            Some((distance.angle, self.radius - distance.norm))
+        if distance.norm() < self.radius {
+            Some((distance.normalize(), self.radius - distance.norm()))
         } else {
             None
         }
@@ -146,86 +193,390 @@ impl Collide<Point> for Rect {
 
 impl Collide<Circle> for Rect {
     fn collide(&self, other: &Circle) -> Option<Collision> {
-        let distance = Vector::from(self.center - other.center);
-        let outer_radius = Point {
-            x: self.width / 2.,
-            y: self.height / 2.,
+        let (half_width, half_height) = (self.width / 2., self.height / 2.);
+        let diff = other.center - self.center;
+
+        // Center of the circle inside the rectangle: push it out through the
+        // closest side, far enough to clear the radius too
+        if diff.x.abs() < half_width && diff.y.abs() < half_height {
+            let dx = half_width + other.radius - diff.x.abs();
+            let dy = half_height + other.radius - diff.y.abs();
+            return Some(if dx < dy {
+                (if diff.x < 0. { LEFT } else { RIGHT }, dx)
+            } else {
+                (if diff.y < 0. { UP } else { DOWN }, dy)
+            });
         }
-        .norm();
 
-        // Fast check
-        if distance.norm > outer_radius + other.radius {
-            return None;
+        // Otherwise push it away from the closest point of the rectangle
+        let closest = Point::new(
+            diff.x.max(-half_width).min(half_width),
+            diff.y.max(-half_height).min(half_height),
+        );
+        let away = diff - closest;
+        if away.norm() < other.radius {
+            Some((away.normalize(), other.radius - away.norm()))
+        } else {
+            None
         }
+    }
+}
 
-        let rect = Rect {
+impl Collide<Rect> for Circle {
+    fn collide(&self, other: &Rect) -> Option<Collision> {
+        other.collide(self)
+    }
+}
+
+/// Entry and exit times of a moving point on one axis of a box, along with the
+/// normal of the side it enters through
+fn slab(
+    origin: Pixels,
+    motion: Pixels,
+    (min, max): (Pixels, Pixels),
+    (min_normal, max_normal): (Vec2, Vec2),
+) -> Option<(f64, f64, Vec2)> {
+    if motion > 0. {
+        Some(((min - origin) / motion, (max - origin) / motion, min_normal))
+    } else if motion < 0. {
+        Some(((max - origin) / motion, (min - origin) / motion, max_normal))
+    } else if origin > min && origin < max {
+        Some((f64::NEG_INFINITY, f64::INFINITY, min_normal))
+    } else {
+        None
+    }
+}
+
+/// First time a moving point enters a box
+fn ray_rect(origin: Point, motion: Point, rect: &Rect) -> Option<Impact> {
+    let (x_in, x_out, x_normal) = slab(
+        origin.x,
+        motion.x,
+        (
+            rect.center.x - rect.width / 2.,
+            rect.center.x + rect.width / 2.,
+        ),
+        (LEFT, RIGHT),
+    )?;
+    let (y_in, y_out, y_normal) = slab(
+        origin.y,
+        motion.y,
+        (
+            rect.center.y - rect.height / 2.,
+            rect.center.y + rect.height / 2.,
+        ),
+        (UP, DOWN),
+    )?;
+
+    let (t_in, normal) = if x_in > y_in {
+        (x_in, x_normal)
+    } else {
+        (y_in, y_normal)
+    };
+    let t_out = f64::min(x_out, y_out);
+
+    if t_in < t_out && (0. ..=1.).contains(&t_in) {
+        Some((t_in, normal))
+    } else {
+        None
+    }
+}
+
+/// First time a moving point enters a circle
+fn ray_circle(origin: Point, motion: Point, circle: &Circle) -> Option<Impact> {
+    let offset = origin - circle.center;
+    let a = motion.x * motion.x + motion.y * motion.y;
+    let b = 2. * (motion.x * offset.x + motion.y * offset.y);
+    let c = offset.x * offset.x + offset.y * offset.y - circle.radius * circle.radius;
+    let discriminant = b * b - 4. * a * c;
+
+    if a == 0. || discriminant <= 0. {
+        return None;
+    }
+
+    let t = (-b - discriminant.sqrt()) / (2. * a);
+    if (0. ..=1.).contains(&t) {
+        Some((t, (origin + motion * t - circle.center).normalize()))
+    } else {
+        None
+    }
+}
+
+fn earliest(a: Option<Impact>, b: Option<Impact>) -> Option<Impact> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(if b.0 < a.0 { b } else { a }),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+impl Sweep<Circle> for Rect {
+    fn sweep(&self, other: &Circle, motion: Point) -> Option<Impact> {
+        // Already overlapping: hit right away, unless on the way out
+        if let Some((normal, _)) = self.collide(other) {
+            return if motion.dot(normal) < 0. {
+                Some((0., normal))
+            } else {
+                None
+            };
+        }
+
+        // The center of the circle hits the rectangle grown by the radius
+        // with rounded corners, which is a cross made of two rectangles plus
+        // a circle on each corner.
+        let tall = Rect {
             width: self.width,
             height: self.height + other.radius * 2.,
             center: self.center,
         };
-
-        // TODO: this might break
-        if let Some(r) = rect.collide(&other.center) {
-            return Some(r);
-        }
-
-        let rect = Rect {
+        let wide = Rect {
             width: self.width + other.radius * 2.,
             height: self.height,
             center: self.center,
         };
 
-        if let Some(r) = rect.collide(&other.center) {
-            return Some(r);
-        }
+        let mut impact = earliest(
+            ray_rect(other.center, motion, &tall),
+            ray_rect(other.center, motion, &wide),
+        );
 
-        let corners = [
-            Circle {
-                // Top left
-                center: Point {
-                    x: self.center.x - self.width / 2.,
-                    y: self.center.y - self.height / 2.,
-                },
-                radius: other.radius,
-            },
-            Circle {
-                // Top right
-                center: Point {
-                    x: self.center.x + self.width / 2.,
-                    y: self.center.y - self.height / 2.,
-                },
-                radius: other.radius,
-            },
-            Circle {
-                // Bottom left
+        for &(dx, dy) in &[(-1., -1.), (1., -1.), (-1., 1.), (1., 1.)] {
+            let corner = Circle {
                 center: Point {
-                    x: self.center.x - self.width / 2.,
-                    y: self.center.y + self.height / 2.,
+                    x: self.center.x + dx * self.width / 2.,
+                    y: self.center.y + dy * self.height / 2.,
                 },
                 radius: other.radius,
-            },
-            Circle {
-                // Bottom right
-                center: Point {
-                    x: self.center.x + self.width / 2.,
-                    y: self.center.y + self.height / 2.,
-                },
-                radius: other.radius,
-            },
-        ];
-
-        for corner in &corners {
-            if let Some(res) = corner.collide(&other.center) {
-                return Some(res);
-            }
+            };
+            impact = earliest(impact, ray_circle(other.center, motion, &corner));
         }
 
-        None
+        impact
     }
 }
 
-impl Collide<Rect> for Circle {
-    fn collide(&self, other: &Rect) -> Option<Collision> {
-        other.collide(self)
+impl Sweep<Circle> for InfiniteWall {
+    fn sweep(&self, other: &Circle, motion: Point) -> Option<Impact> {
+        let (t, normal) = match self.orientation {
+            WallOrientation::Top if motion.y < 0. => (
+                (self.center.y + other.radius - other.center.y) / motion.y,
+                DOWN,
+            ),
+            WallOrientation::Left if motion.x < 0. => (
+                (self.center.x + other.radius - other.center.x) / motion.x,
+                RIGHT,
+            ),
+            WallOrientation::Bottom if motion.y > 0. => (
+                (self.center.y - other.radius - other.center.y) / motion.y,
+                UP,
+            ),
+            WallOrientation::Right if motion.x > 0. => (
+                (self.center.x - other.radius - other.center.x) / motion.x,
+                LEFT,
+            ),
+            _ => return None,
+        };
+
+        // Negative if the circle is already past the wall
+        if t <= 1. {
+            Some((t.max(0.), normal))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod shape_test {
+    use super::*;
+
+    fn brick() -> Rect {
+        Rect::new(Point::new(100., 100.), 32., 16.)
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} is not {}", a, b);
+    }
+
+    #[test]
+    fn test_sweep_tunneling() {
+        // Moves 100px in one step, which is way more than the brick height
+        let ball = Circle::new(Point::new(100., 150.), 8.);
+        let (t, normal) = brick().sweep(&ball, Point::new(0., -100.)).unwrap();
+        assert_close(t, 0.34);
+        assert_eq!(normal, DOWN);
+    }
+
+    #[test]
+    fn test_sweep_sides() {
+        let ball = Circle::new(Point::new(50., 100.), 8.);
+        let (t, normal) = brick().sweep(&ball, Point::new(40., 0.)).unwrap();
+        assert_close(t, 0.65);
+        assert_eq!(normal, LEFT);
+
+        let ball = Circle::new(Point::new(100., 50.), 8.);
+        let (t, normal) = brick().sweep(&ball, Point::new(0., 40.)).unwrap();
+        assert_close(t, 0.85);
+        assert_eq!(normal, UP);
+    }
+
+    #[test]
+    fn test_sweep_corner() {
+        // Heading straight at the bottom right corner
+        let ball = Circle::new(Point::new(146., 138.), 8.);
+        let (t, normal) = brick().sweep(&ball, Point::new(-30., -30.)).unwrap();
+        assert!(t > 0. && t < 1.);
+        assert!((normal - Vec2::new(1., 1.).normalize()).norm() < 1e-9);
+    }
+
+    #[test]
+    fn test_sweep_miss() {
+        let ball = Circle::new(Point::new(100., 150.), 8.);
+        // Moving away
+        assert!(brick().sweep(&ball, Point::new(0., 100.)).is_none());
+        // Not going far enough
+        assert!(brick().sweep(&ball, Point::new(0., -10.)).is_none());
+        // Passing by
+        assert!(brick().sweep(&ball, Point::new(100., -100.)).is_none());
+    }
+
+    #[test]
+    fn test_collide_circle() {
+        // Center inside the brick, close to its left side: pushed out far
+        // enough to clear it
+        let ball = Circle::new(Point::new(85., 100.), 8.);
+        let (normal, depth) = brick().collide(&ball).unwrap();
+        assert_eq!(normal, LEFT);
+        assert_close(depth, 9.);
+
+        // Overlapping the top side
+        let ball = Circle::new(Point::new(105., 86.), 8.);
+        let (normal, depth) = brick().collide(&ball).unwrap();
+        assert_eq!(normal, UP);
+        assert_close(depth, 2.);
+
+        assert!(brick()
+            .collide(&Circle::new(Point::new(122., 114.), 8.))
+            .is_none());
+    }
+
+    #[test]
+    fn test_sweep_overlap() {
+        // Starts the step inside the bottom of the brick
+        let ball = Circle::new(Point::new(100., 110.), 8.);
+        let (t, normal) = brick().sweep(&ball, Point::new(0., -10.)).unwrap();
+        assert_eq!(t, 0.);
+        assert_eq!(normal, DOWN);
+        // Already leaving
+        assert!(brick().sweep(&ball, Point::new(0., 10.)).is_none());
+
+        // Overlapping a corner pushes away from it
+        let ball = Circle::new(Point::new(120., 112.), 8.);
+        let (normal, _) = brick().collide(&ball).unwrap();
+        assert!(normal.x > 0. && normal.y > 0.);
+
+        // Already past a wall
+        let wall = InfiniteWall {
+            orientation: WallOrientation::Right,
+            center: Point::new(200., 0.),
+            gap: None,
+        };
+        let ball = Circle::new(Point::new(205., 100.), 8.);
+        assert_eq!(wall.sweep(&ball, Point::new(10., 0.)).unwrap(), (0., LEFT));
+        assert!(wall.sweep(&ball, Point::new(-10., 0.)).is_none());
+    }
+
+    #[test]
+    fn test_sweep_wall() {
+        let wall = InfiniteWall {
+            orientation: WallOrientation::Right,
+            center: Point::new(200., 0.),
+            gap: None,
+        };
+        let ball = Circle::new(Point::new(100., 100.), 8.);
+        let (t, normal) = wall.sweep(&ball, Point::new(184., 40.)).unwrap();
+        assert_close(t, 0.5);
+        assert_eq!(normal, LEFT);
+        assert!(wall.sweep(&ball, Point::new(-184., 40.)).is_none());
+        assert!(wall.sweep(&ball, Point::new(50., 40.)).is_none());
+    }
+
+    #[test]
+    fn test_bounce_walls() {
+        use rand::{Rng, SeedableRng};
+        use rand_pcg::Pcg32;
+        use utils::PI;
+
+        let mut rng = Pcg32::seed_from_u64(0);
+        let center = Point::new(200., 200.);
+        // Where each wall is, and which way is back inside
+        let walls = [
+            (
+                WallOrientation::Top,
+                Point::new(0., 100.),
+                Vec2::new(0., 1.),
+            ),
+            (
+                WallOrientation::Bottom,
+                Point::new(0., 300.),
+                Vec2::new(0., -1.),
+            ),
+            (
+                WallOrientation::Left,
+                Point::new(100., 0.),
+                Vec2::new(1., 0.),
+            ),
+            (
+                WallOrientation::Right,
+                Point::new(300., 0.),
+                Vec2::new(-1., 0.),
+            ),
+        ];
+        for (orientation, position, inside) in &walls {
+            let wall = InfiniteWall {
+                orientation: orientation.clone(),
+                center: *position,
+                gap: None,
+            };
+            for _ in 0..200 {
+                let ball = Circle::new(center, 8.);
+                let motion = Vec2::from_angle(rng.gen_range(-PI, PI)) * 400.;
+                let (_, normal) = match wall.sweep(&ball, motion) {
+                    Some(impact) => impact,
+                    None => continue,
+                };
+                // Sent back inside, as fast as it came and with the same
+                // speed along the wall
+                let bounced = motion.reflect(normal);
+                assert!((bounced.norm() - motion.norm()).abs() < 1e-9);
+                assert!((bounced.dot(*inside) + motion.dot(*inside)).abs() < 1e-9);
+                assert!(bounced.dot(*inside) > 0.);
+            }
+        }
+    }
+
+    #[test]
+    fn test_wall_gap() {
+        let wall = InfiniteWall {
+            orientation: WallOrientation::Right,
+            center: Point::new(200., 0.),
+            gap: Some((100., 150.)),
+        };
+
+        // Fits in the gap
+        let paddle = Rect::new(Point::new(200., 125.), 80., 16.);
+        assert!(wall.collide(&paddle).is_none());
+
+        // Half of it sticks out of the gap
+        let paddle = Rect::new(Point::new(200., 150.), 80., 16.);
+        assert!(wall.collide(&paddle).is_some());
+
+        // Balls bounce off the gap like off the rest of the wall
+        let ball = Circle::new(Point::new(100., 125.), 8.);
+        let (t, normal) = wall.sweep(&ball, Point::new(200., 0.)).unwrap();
+        assert_close(t, 0.46);
+        assert_eq!(normal, LEFT);
+        let ball = Circle::new(Point::new(195., 125.), 8.);
+        assert!(wall.collide(&ball).is_some());
     }
 }
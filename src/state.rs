@@ -1,37 +1,118 @@
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+#[cfg(feature = "render")]
 use sdl2::render::{Canvas, RenderTarget, Texture};
 use std::collections::HashMap;
 
-use ball::{Ball, BALL_RADIUS};
+use ball::{Ball, Speed, BALL_RADIUS};
 use bonus::{ActiveBonus, BonusType, FallingBonus};
 use brick::Brick;
+use broadphase::{sweep_and_prune, Grid};
+use effect::Effect;
+use laser::{Bolt, BOLT_HEIGHT, BOLT_WIDTH};
 use level::Level;
-use player::{Player, PLAYER_THICKNESS};
+use player::{Deflection, Player, PLAYER_THICKNESS};
+#[cfg(feature = "render")]
 use resize::RenderContext;
-use traits::{Collide, Renderable, Updatable};
-use utils::{Point, PI};
+use shape::WallOrientation;
+#[cfg(feature = "render")]
+use traits::Renderable;
+use traits::{Collide, Impact, Sweep, Updatable};
+use utils::{Pixels, Point, PI};
 use wall::{Wall, WALL_THICKNESS};
 
 const MAX_BALLS: usize = 16;
+/// Size of the cells of the grid used to look bricks up
+const GRID_CELL: Pixels = 64.;
+/// Maximum number of hits resolved for a single ball during one step
+const MAX_IMPACTS: usize = 4;
+/// How far a ball is pushed off a surface after hitting it, so that it does
+/// not start the next sweep overlapping it
+const CONTACT_SKIN: f64 = 1e-3;
+/// Time between two laser shots
+const LASER_COOLDOWN: f64 = 0.3;
+/// Points for catching a falling bonus
+const BONUS_POINTS: u64 = 1000;
+/// Points for leaving the level through the warp gate
+const WARP_POINTS: u64 = 10000;
+/// Bricks to destroy in a row with the same ball to raise the score multiplier
+const COMBO_STEP: u32 = 4;
+const MAX_MULTIPLIER: u32 = 4;
+
+/// Score multiplier for a ball that already destroyed `combo` bricks since it
+/// last touched the paddle
+/// Bricks that a ball at `position` could hit while moving by `motion`
+fn nearby_bricks(grid: &Grid, position: Point, motion: Point) -> Vec<usize> {
+    let to = position + motion;
+    let reach = Point::new(BALL_RADIUS, BALL_RADIUS);
+    grid.query(
+        Point::new(position.x.min(to.x), position.y.min(to.y)) - reach,
+        Point::new(position.x.max(to.x), position.y.max(to.y)) + reach,
+    )
+}
+
+fn multiplier(combo: u32) -> u32 {
+    u32::min(1 + combo / COMBO_STEP, MAX_MULTIPLIER)
+}
+
+/// Player controls for one step
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+pub struct Input {
+    /// Paddle acceleration, from -1 (left) to 1 (right)
+    pub direction: f64,
+    /// Shoot with the laser, for as long as it is held
+    pub fire: bool,
+    /// Release the balls held on the paddle, only when the key is pressed
+    pub release: bool,
+}
 
 pub struct State {
+    /// Destroyed bricks stay in place until they make up half of them, so
+    /// that their indices in the grid stay valid
     bricks: Vec<Brick>,
+    /// Destroyed bricks not cleaned up yet
+    dead_bricks: usize,
+    /// Finds the bricks near a point without going through all of them. Must
+    /// be rebuilt whenever `bricks` is cleaned up.
+    grid: Grid,
     walls: Vec<Wall>,
     pit: Wall,
     bonuses: Vec<FallingBonus>,
     active_bonuses: Vec<ActiveBonus>,
     player: Player,
+    deflection: Deflection,
+    speed: Speed,
+    ball_collisions: bool,
     lives: u8,
+    score: u64,
     balls: Vec<Ball>,
+    bolts: Vec<Bolt>,
+    /// Animations played over the game, which don't take part in it
+    effects: Vec<Effect>,
+    firing: bool,
+    laser_cooldown: f64,
+    /// Number of times the balls were split by Divide
+    splits: u32,
+    warped: bool,
+    seed: u64,
+    rng: Pcg32,
 }
 
-const PLAYER_OFFSET: f64 = WALL_THICKNESS + PLAYER_THICKNESS / 2. + 10.;
-const BALL_OFFSET: f64 = PLAYER_OFFSET + PLAYER_THICKNESS / 2. + BALL_RADIUS;
+/// Height of the center of the paddle above the bottom of the level
+pub const PLAYER_OFFSET: f64 = WALL_THICKNESS + PLAYER_THICKNESS / 2. + 10.;
+/// Height of the center of the ball above the bottom of the level, at launch
+pub const BALL_OFFSET: f64 = PLAYER_OFFSET + PLAYER_THICKNESS / 2. + BALL_RADIUS;
 
 impl State {
-    pub fn new(level: Level) -> State {
+    /// Create a new game from a level. Every random decision taken during the
+    /// game is derived from `seed`, so two games with the same seed and inputs
+    /// play out exactly the same.
+    pub fn new(level: Level, seed: u64) -> State {
+        let bricks = State::level_bricks(&level);
         State {
-            bricks: level.bricks.clone(),
+            grid: State::brick_grid(&bricks),
+            bricks,
+            dead_bricks: 0,
             walls: Wall::make_walls(level.height() as f64, level.width() as f64),
             pit: Wall::pit(level.height() as f64, level.width() as f64),
             bonuses: Vec::new(),
@@ -40,27 +121,148 @@ impl State {
                 level.width() as f64 * 0.5,
                 level.height() as f64 - PLAYER_OFFSET,
             )),
+            deflection: level.deflection(),
+            speed: level.speed(),
+            ball_collisions: level.ball_collisions(),
             lives: 3,
+            score: 0,
             balls: vec![Ball::new(
                 Point::new(
                     level.width() as f64 * 0.5,
                     level.height() as f64 - BALL_OFFSET,
                 ),
                 -PI / 4.0,
+                level.speed(),
             )],
+            bolts: Vec::new(),
+            effects: Vec::new(),
+            firing: false,
+            laser_cooldown: 0.,
+            splits: 0,
+            warped: false,
+            seed,
+            rng: Pcg32::seed_from_u64(seed),
+        }
+    }
+
+    /// Move on to another level, keeping the lives, the score and the random
+    /// generator
+    pub fn load_level(&mut self, level: Level) {
+        let next = State::new(level, self.seed);
+        *self = State {
+            lives: self.lives,
+            score: self.score,
+            rng: self.rng.clone(),
+            ..next
+        };
+    }
+
+    /// Replace the bricks with the ones of `level`, leaving everything else
+    /// running
+    pub fn patch_bricks(&mut self, level: &Level) {
+        self.set_bricks(State::level_bricks(level));
+    }
+
+    fn set_bricks(&mut self, bricks: Vec<Brick>) {
+        self.grid = State::brick_grid(&bricks);
+        self.bricks = bricks;
+        self.dead_bricks = 0;
+    }
+
+    /// Take the bricks just destroyed out of the grid. Everything is rebuilt
+    /// without the destroyed bricks once they are half of them.
+    fn remove_bricks(&mut self, destroyed: &[usize]) {
+        for &i in destroyed {
+            let (min, max) = self.bricks[i].bounds();
+            self.grid.remove(i, min, max);
+        }
+        self.dead_bricks += destroyed.len();
+        if self.dead_bricks * 2 > self.bricks.len() {
+            let bricks = self.bricks.drain(..).filter(Brick::alive).collect();
+            self.set_bricks(bricks);
         }
     }
 
+    fn brick_grid(bricks: &[Brick]) -> Grid {
+        let boxes: Vec<(Point, Point)> = bricks.iter().map(Brick::bounds).collect();
+        Grid::new(&boxes, GRID_CELL)
+    }
+
+    fn level_bricks(level: &Level) -> Vec<Brick> {
+        level
+            .bricks
+            .iter()
+            .map(|b| {
+                let mut brick = b.clone();
+                brick.set_points(level.points(b.brick_type()));
+                brick.scale_hitpoints(level.number());
+                brick
+            })
+            .collect()
+    }
+
+    /// Make balls bounce off each other, whatever the level says
+    pub fn ball_collisions(&self) -> bool {
+        self.ball_collisions
+    }
+
+    pub fn set_ball_collisions(&mut self, enabled: bool) {
+        self.ball_collisions = enabled;
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn lives(&self) -> u8 {
+        self.lives
+    }
+
+    pub fn score(&self) -> u64 {
+        self.score
+    }
+
+    /// Timed bonuses that are still running
+    pub fn active_bonuses(&self) -> Vec<&ActiveBonus> {
+        self.active_bonuses.iter().filter(|b| b.active()).collect()
+    }
+
+    pub fn bricks(&self) -> Vec<&Brick> {
+        self.bricks.iter().filter(|b| b.alive()).collect()
+    }
+
     pub fn alive(&self) -> bool {
         self.lives > 0
     }
 
     pub fn won(&self) -> bool {
-        !self.bricks.iter().any(|b| b.breakable)
+        !self.bricks.iter().any(|b| b.breakable && b.alive())
     }
 
-    pub fn input(&mut self, input: f64) {
-        self.player.input(input);
+    /// The player left through the warp gate opened by the Break bonus
+    pub fn warped(&self) -> bool {
+        self.warped
+    }
+
+    /// The level is over, one way or another, and the next one can start
+    pub fn cleared(&self) -> bool {
+        self.won() || self.warped()
+    }
+
+    pub fn input(&mut self, input: Input) {
+        self.player.input(input.direction);
+        self.firing = input.fire;
+        if input.release {
+            for ball in &mut self.balls {
+                ball.release();
+            }
+        }
+    }
+
+    fn bonus_active(&self, bonus: BonusType) -> bool {
+        self.active_bonuses
+            .iter()
+            .any(|b| b.bonus_type == bonus && b.active())
     }
 
     fn queue_bonus(&mut self, b: ActiveBonus) {
@@ -69,34 +271,95 @@ impl State {
 
     fn activate_bonus(&mut self, bonus: BonusType) {
         match bonus {
-            BonusType::Slow => self.queue_bonus(ActiveBonus::from(bonus)),
-            BonusType::Expand => self.player.grow(),
+            BonusType::Slow | BonusType::Catch | BonusType::Laser | BonusType::Expand => {
+                self.queue_bonus(ActiveBonus::from(bonus))
+            }
             BonusType::Divide => {
                 let mut to_add = Vec::new();
-                for ball in &self.balls {
-                    let mut new = ball.clone();
-                    new.rotate(PI / 6.);
-                    to_add.push(new);
-                    let mut new = ball.clone();
-                    new.rotate(-PI / 6.);
-                    to_add.push(new);
+                // New balls start where the original one is, and don't bounce
+                // off it until they got apart
+                for ball in &mut self.balls {
+                    self.splits += 1;
+                    ball.set_split(Some(self.splits));
+                    for &angle in &[PI / 6., -PI / 6.] {
+                        let mut new = ball.clone();
+                        new.rotate(angle);
+                        to_add.push(new);
+                    }
                 }
                 self.balls.extend(to_add);
                 self.balls.truncate(MAX_BALLS);
             }
+            BonusType::Break => self.open_warp(),
             BonusType::Life => {
                 self.lives += 1;
             }
         }
     }
 
+    /// Open a gate in the right wall, in front of the paddle
+    fn open_warp(&mut self) {
+        let y = self.player.position().y;
+        for wall in &mut self.walls {
+            if let WallOrientation::Right = wall.shape.orientation {
+                wall.open(y - PLAYER_THICKNESS, y + PLAYER_THICKNESS);
+            }
+        }
+    }
+
+    /// Shoot a pair of bolts from both ends of the paddle
+    fn fire_laser(&mut self) {
+        let x = self.player.width() / 2. - BOLT_WIDTH;
+        let y = -(PLAYER_THICKNESS + BOLT_HEIGHT) / 2.;
+        for &x in &[-x, x] {
+            self.bolts
+                .push(Bolt::new(self.player.position() + Point::new(x, y)));
+        }
+        self.laser_cooldown = LASER_COOLDOWN;
+    }
+
+    /// Bounce the balls off each other, only checking the pairs that overlap
+    /// along the x axis
+    fn collide_balls(&mut self) {
+        let intervals: Vec<(Pixels, Pixels)> = self
+            .balls
+            .iter()
+            .map(|b| (b.position().x - BALL_RADIUS, b.position().x + BALL_RADIUS))
+            .collect();
+        for (i, j) in sweep_and_prune(&intervals) {
+            let (left, right) = self.balls.split_at_mut(j);
+            let (a, b) = (&mut left[i], &mut right[0]);
+            if a.on_hold() || b.on_hold() || (a.split().is_some() && a.split() == b.split()) {
+                continue;
+            }
+            if let Some(collision) = a.shape().collide(&b.shape()) {
+                a.collide_ball(b, collision);
+            }
+        }
+
+        // Split balls collide again once they left the others of their split
+        for i in 0..self.balls.len() {
+            let ball = &self.balls[i];
+            let apart = ball.split().is_some()
+                && self.balls.iter().enumerate().all(|(j, other)| {
+                    i == j
+                        || other.split() != ball.split()
+                        || ball.shape().collide(&other.shape()).is_none()
+                });
+            if apart {
+                self.balls[i].set_split(None);
+            }
+        }
+    }
+
     fn bonus_stack(&mut self, bonus: BonusType, count: usize) {
         match bonus {
             BonusType::Slow => {
                 for ref mut ball in &mut self.balls {
-                    ball.speed(count);
+                    ball.slow_down(count);
                 }
             }
+            BonusType::Expand => self.player.expand(count),
             _ => {}
         }
     }
@@ -104,10 +367,11 @@ impl State {
 
 impl Default for State {
     fn default() -> Self {
-        Self::new(Level::default())
+        Self::new(Level::default(), 0)
     }
 }
 
+#[cfg(feature = "render")]
 impl<T> Renderable<T> for State
 where
     T: RenderTarget,
@@ -118,7 +382,7 @@ where
         context: &RenderContext,
         texture: &Texture,
     ) -> Result<(), failure::Error> {
-        for brick in &self.bricks {
+        for brick in self.bricks() {
             brick.render(canvas, context, texture)?;
         }
         for wall in &self.walls {
@@ -130,20 +394,39 @@ where
         for ball in &self.balls {
             ball.render(canvas, context, texture)?;
         }
+        for bolt in &self.bolts {
+            bolt.render(canvas, context, texture)?;
+        }
+        for effect in &self.effects {
+            effect.render(canvas, context, texture)?;
+        }
         self.player.render(canvas, context, texture)?;
         Ok(())
     }
 }
 
+/// Does nothing once the game is over, so that it can be stepped for longer
+/// than the game lasts.
 impl Updatable for State {
     fn update(&mut self, dt: f64) {
-        // First, update all the balls
+        if !self.alive() {
+            return;
+        }
+
+        // Play the effects, and drop the ones that are over
+        for effect in &mut self.effects {
+            effect.update(dt);
+        }
+        self.effects.retain(|e| !e.done());
+
+        // Then, update all the balls
         for ref mut ball in &mut self.balls {
             ball.update(dt);
             // Force the position of balls that are on hold
             if ball.on_hold() {
                 ball.set_position(
-                    self.player.position() + Point::new(0., -(PLAYER_THICKNESS / 2. + BALL_RADIUS)),
+                    self.player.position()
+                        + Point::new(ball.hold_offset(), -(PLAYER_THICKNESS / 2. + BALL_RADIUS)),
                 );
             }
         }
@@ -151,28 +434,145 @@ impl Updatable for State {
         // Update the player
         self.player.update(dt);
 
-        // Check for collisions between bricks and balls
-        for brick in &mut self.bricks {
-            for ref mut ball in &mut self.balls {
-                if let Some(collision) = brick.shape().collide(&ball.shape()) {
-                    ball.bounce(collision);
-                    brick.damage();
+        // Bricks destroyed during this step
+        let mut destroyed: Vec<usize> = Vec::new();
+
+        // Move the balls, resolving hits with bricks and walls in the order they
+        // happen during the step
+        for ball in &mut self.balls {
+            if ball.on_hold() {
+                continue;
+            }
+
+            let mut left = dt;
+            for _ in 0..MAX_IMPACTS {
+                let shape = ball.shape();
+                let motion = ball.motion(left);
 
-                    // Randomly spawn a new bonus
-                    if !brick.alive() && rand::thread_rng().gen_bool(1. / 4.) {
-                        self.bonuses.push(FallingBonus::random(brick.center));
+                // Find the first brick or wall on the way (`None` is a wall),
+                // among the bricks around the path of the ball
+                let nearby = nearby_bricks(&self.grid, ball.position(), motion);
+                let bricks = &self.bricks;
+                let bricks = nearby
+                    .into_iter()
+                    .filter(|&i| bricks[i].alive())
+                    .filter_map(|i| {
+                        bricks[i]
+                            .shape()
+                            .sweep(&shape, motion)
+                            .map(|h| (h, Some(i)))
+                    });
+                let walls = self
+                    .walls
+                    .iter()
+                    .filter_map(|w| w.shape.sweep(&shape, motion).map(|h| (h, None)));
+                let first: Option<(Impact, Option<usize>)> = bricks
+                    .chain(walls)
+                    .min_by(|((a, _), _), ((b, _), _)| a.partial_cmp(b).unwrap());
+
+                let ((t, normal), hit) = match first {
+                    Some(first) => first,
+                    None => break,
+                };
+
+                ball.travel(left * t);
+                ball.bounce((normal, CONTACT_SKIN));
+                left *= 1. - t;
+
+                if let Some(i) = hit {
+                    let brick = &mut self.bricks[i];
+                    brick.damage();
+                    ball.hit();
+                    if !brick.alive() {
+                        destroyed.push(i);
+                        self.effects.push(Effect::explosion(brick));
+                        self.score += u64::from(brick.points() * multiplier(ball.combo()));
+                        ball.count_brick();
+                    } else {
+                        self.effects.extend(Effect::flash(brick));
                     }
                 }
             }
+            ball.travel(left);
+        }
+
+        // Shoot with the laser…
+        self.laser_cooldown -= dt;
+        if self.firing && self.laser_cooldown <= 0. && self.bonus_active(BonusType::Laser) {
+            self.fire_laser();
+        }
+
+        // …and move the bolts, which stop on the first brick or wall they hit
+        for bolt in &mut self.bolts {
+            bolt.update(dt);
+        }
+        let bricks = &mut self.bricks;
+        let grid = &self.grid;
+        let walls = &self.walls;
+        let score = &mut self.score;
+        let effects = &mut self.effects;
+        self.bolts.retain(|bolt| {
+            let half = Point::new(BOLT_WIDTH / 2., BOLT_HEIGHT / 2.);
+            let hit = grid
+                .query(bolt.position() - half, bolt.position() + half)
+                .into_iter()
+                .find(|&i| bricks[i].alive() && bolt.collide(&bricks[i].shape()).is_some());
+            if let Some(i) = hit {
+                let brick = &mut bricks[i];
+                brick.damage();
+                if !brick.alive() {
+                    destroyed.push(i);
+                    effects.push(Effect::explosion(brick));
+                    *score += u64::from(brick.points());
+                } else {
+                    effects.extend(Effect::flash(brick));
+                }
+                false
+            } else {
+                !walls
+                    .iter()
+                    .any(|w| w.shape.collide(&bolt.shape()).is_some())
+            }
+        });
+
+        // Randomly spawn new bonuses where bricks were destroyed
+        for &i in &destroyed {
+            if self.rng.gen_bool(1. / 4.) {
+                let position = self.bricks[i].center;
+                self.bonuses
+                    .push(FallingBonus::random(position, &mut self.rng));
+            }
         }
 
         // Remove bricks that were destroyed
-        self.bricks.retain(Brick::alive);
+        self.remove_bricks(&destroyed);
+
+        if self.ball_collisions {
+            self.collide_balls();
+        }
 
         // Check for collisions between balls and the player
+        let catching = self.bonus_active(BonusType::Catch);
         for ref mut ball in &mut self.balls {
+            if ball.on_hold() {
+                continue;
+            }
             if let Some(collision) = self.player.shape().collide(&ball.shape()) {
-                ball.bounce(collision);
+                ball.reset_combo();
+                // Send the ball back depending on where it landed on the paddle
+                let half_width = self.player.width() / 2.;
+                let offset = (ball.position().x - self.player.position().x)
+                    .max(-half_width)
+                    .min(half_width);
+                let angle = self
+                    .deflection
+                    .angle(offset / half_width, self.player.velocity());
+                if catching {
+                    // Stick the ball where it landed, and launch it later
+                    ball.catch(offset, angle);
+                } else {
+                    ball.deflect(angle, collision);
+                }
             }
         }
 
@@ -199,6 +599,7 @@ impl Updatable for State {
         });
 
         for b in to_activate {
+            self.score += BONUS_POINTS;
             self.activate_bonus(b);
         }
 
@@ -229,21 +630,393 @@ impl Updatable for State {
         if self.balls.is_empty() {
             // Lost a life
             self.lives -= 1;
-            self.balls.push(Ball::new(Point::new(0., 0.), -PI / 4.0));
+            self.balls
+                .push(Ball::new(Point::new(0., 0.), -PI / 4.0, self.speed));
         }
 
+        // Balls were already kept inside the walls by the sweep
         for wall in &self.walls {
-            // Check for collisions between walls and the balls
-            for ref mut ball in &mut self.balls {
-                if let Some(collision) = wall.shape.collide(&ball.shape()) {
-                    ball.bounce(collision);
+            // Check for collisions between the walls and the player
+            if let Some(collision) = wall.shape.collide(&self.player.shape()) {
+                self.player.bounce(collision);
+            }
+
+            // The player can only get past the right wall through the warp gate
+            if let WallOrientation::Right = wall.shape.orientation {
+                if self.player.position().x > wall.shape.center.x && !self.warped {
+                    self.warped = true;
+                    self.score += WARP_POINTS;
                 }
             }
+        }
+    }
+}
 
-            // …and between the walls and the player
-            if let Some(collision) = wall.shape.collide(&self.player.shape()) {
-                self.player.bounce(collision);
+#[cfg(test)]
+mod state_test {
+    use super::*;
+    use brick::BrickType;
+    use rand::{Rng, SeedableRng};
+    use rand_pcg::Pcg32;
+    use std::time::{Duration, Instant};
+    use timestep::FixedStep;
+    use utils::Vec2;
+
+    #[test]
+    fn test_headless_game() {
+        let mut state = State::default();
+        let bricks = state.bricks().len();
+
+        // Step ten seconds of game time without any input
+        for _ in 0..600 {
+            state.update(1. / 60.);
+        }
+
+        assert!(state.bricks().len() < bricks);
+    }
+
+    #[test]
+    fn test_past_game_over() {
+        let mut state = State::new(Level::default(), 0);
+        let mut steps = 0;
+        while state.alive() {
+            state.update(1. / 120.);
+            steps += 1;
+            assert!(steps < 120 * 600, "the game never ended");
+        }
+
+        // Keeps going without losing more lives
+        for _ in 0..1200 {
+            state.update(1. / 120.);
+        }
+        assert_eq!(state.lives(), 0);
+    }
+
+    #[test]
+    fn test_frame_rate_independence() {
+        let run = |frame_hz: u32| {
+            let mut stepper = FixedStep::new(128);
+            let mut state = State::new(Level::default(), 7);
+            let frame = Duration::from_secs(1) / frame_hz;
+            let mut steps = 0;
+            for _ in 0..(frame_hz * 10) {
+                steps += stepper.advance(frame, &mut state);
+            }
+            (steps, state)
+        };
+
+        let (native_steps, native) = run(128);
+        for &hz in &[32, 64, 256] {
+            let (steps, state) = run(hz);
+            assert_eq!(steps, native_steps);
+            assert_eq!(state.bricks().len(), native.bricks().len());
+            assert_eq!(state.lives, native.lives);
+        }
+    }
+
+    #[test]
+    fn test_catch() {
+        let mut state = State::default();
+        state.activate_bonus(BonusType::Catch);
+
+        // Drop a ball on the right half of the paddle
+        let mut ball = Ball::new(
+            state.player.position() + Point::new(20., -40.),
+            PI / 2.,
+            Speed::default(),
+        );
+        ball.release();
+        state.balls = vec![ball];
+
+        for _ in 0..60 {
+            state.update(1. / 120.);
+        }
+        assert!(state.balls[0].on_hold());
+        let offset = state.balls[0].hold_offset();
+        assert!(offset > 15. && offset < 25.);
+
+        // Holding fire down does not let go of the ball…
+        let fire = Input {
+            direction: 0.,
+            fire: true,
+            release: false,
+        };
+        state.input(fire);
+        state.update(1. / 120.);
+        assert!(state.balls[0].on_hold());
+
+        // …pressing it does
+        state.input(Input {
+            release: true,
+            ..fire
+        });
+        state.update(1. / 120.);
+        let ball = &state.balls[0];
+        assert!(!ball.on_hold());
+        // Leaves upwards, leaning right
+        assert!(ball.velocity.y < 0. && ball.velocity.x > 0.);
+    }
+
+    #[test]
+    fn test_laser() {
+        let mut state = State::default();
+        let target = state.player.position() + Point::new(-40. + BOLT_WIDTH, -300.);
+        state.set_bricks(vec![
+            Brick::new(BrickType::Simple, target, 32., 16.),
+            Brick::new(BrickType::Super, Point::new(100., 100.), 32., 16.),
+        ]);
+
+        state.fire_laser();
+        assert_eq!(state.bolts.len(), 2);
+        for _ in 0..240 {
+            state.update(1. / 120.);
+        }
+
+        // One bolt broke the brick, the other one hit the ceiling
+        assert_eq!(state.bricks().len(), 1);
+        assert!(state.bolts.is_empty());
+
+        // The broken brick is only cleaned up once more than half are gone
+        assert_eq!(state.bricks.len(), 2);
+        state.bricks[1].breakable = true;
+        state.bricks[1].damage();
+        state.remove_bricks(&[1]);
+        assert!(state.bricks.is_empty());
+        assert!(state.won());
+    }
+
+    #[test]
+    fn test_effects() {
+        let mut state = State::default();
+        let left = state.player.position() + Point::new(-40. + BOLT_WIDTH, -300.);
+        let right = state.player.position() + Point::new(40. - BOLT_WIDTH, -300.);
+        state.set_bricks(vec![
+            Brick::new(BrickType::Silver, left, 32., 16.),
+            Brick::new(BrickType::Simple, right, 32., 16.),
+        ]);
+
+        state.fire_laser();
+        while !state.bolts.is_empty() {
+            state.update(1. / 120.);
+        }
+
+        // The silver brick flashes, the other one explodes
+        assert_eq!(state.bricks().len(), 1);
+        assert_eq!(state.effects.len(), 2);
+
+        for _ in 0..60 {
+            state.update(1. / 120.);
+        }
+        assert!(state.effects.is_empty());
+        assert_eq!(state.bricks().len(), 1);
+    }
+
+    #[test]
+    fn test_expand() {
+        let mut state = State::default();
+        let width = state.player.width();
+
+        state.activate_bonus(BonusType::Expand);
+        for _ in 0..4 {
+            state.update(1. / 120.);
+        }
+        assert!(state.player.width() > width);
+        assert!(state.player.width() < width + 20.);
+
+        // Fully grown after a while, then back to normal when it wears off
+        for _ in 0..120 {
+            state.update(1. / 120.);
+        }
+        assert_eq!(state.player.width(), width + 20.);
+        for _ in 0..1200 {
+            state.update(1. / 120.);
+        }
+        assert_eq!(state.player.width(), width);
+    }
+
+    #[test]
+    fn test_ball_collisions() {
+        let mut state = State::default();
+        state.set_bricks(Vec::new());
+        state.set_ball_collisions(true);
+        let ball = |x, angle| {
+            let mut ball = Ball::new(Point::new(x, 300.), angle, Speed::default());
+            ball.release();
+            ball
+        };
+        state.balls = vec![ball(200., 0.), ball(300., PI)];
+
+        // Head-on: they swap velocities
+        for _ in 0..30 {
+            state.update(1. / 120.);
+        }
+        assert!(state.balls[0].position().x < 200.);
+        assert!(state.balls[1].position().x > 300.);
+        assert!((state.balls[0].velocity - Vec2::new(-400., 0.)).norm() < 1e-6);
+        assert!((state.balls[1].velocity - Vec2::new(400., 0.)).norm() < 1e-6);
+    }
+
+    /// 64 × 64 bricks, with 16 balls flying around under them
+    fn many_bricks() -> State {
+        let (width, height) = (1280. + WALL_THICKNESS * 2., 1000.);
+        let mut state = State {
+            walls: Wall::make_walls(height, width),
+            pit: Wall::pit(height, width),
+            ..State::default()
+        };
+        let mut bricks = Vec::new();
+        for row in 0..64 {
+            for column in 0..64 {
+                let center = Point::new(
+                    WALL_THICKNESS + 10. + f64::from(column) * 20.,
+                    WALL_THICKNESS + 5. + f64::from(row) * 10.,
+                );
+                bricks.push(Brick::new(BrickType::Simple, center, 20., 10.));
+            }
+        }
+        state.set_bricks(bricks);
+        let mut rng = Pcg32::seed_from_u64(0);
+        state.balls = (0..16)
+            .map(|i| {
+                let position = Point::new(100. + f64::from(i) * 70., 800.);
+                let mut ball = Ball::new(position, rng.gen_range(-PI, 0.), Speed::default());
+                ball.release();
+                ball
+            })
+            .collect();
+
+        state
+    }
+
+    #[test]
+    fn test_many_bricks() {
+        let mut state = many_bricks();
+
+        // Each ball only looks at the bricks of the 2 × 2 cells around it at
+        // most, which is 5 × 8 bricks per cell, out of thousands
+        for _ in 0..240 {
+            for ball in &state.balls {
+                let nearby = nearby_bricks(&state.grid, ball.position(), ball.motion(1. / 120.));
+                assert!(nearby.len() <= 4 * 5 * 8, "{} bricks", nearby.len());
             }
+            state.update(1. / 120.);
+        }
+        assert!(state.bricks().len() < 64 * 64);
+    }
+
+    /// Timing depends on the machine: run with `cargo test --release -- --ignored`
+    #[test]
+    #[ignore]
+    fn test_many_bricks_timing() {
+        let mut state = many_bricks();
+        for _ in 0..240 {
+            let start = Instant::now();
+            state.update(1. / 120.);
+            assert!(start.elapsed() < Duration::from_millis(1));
         }
     }
+
+    #[test]
+    fn test_divide() {
+        let mut state = State::default();
+        state.set_bricks(Vec::new());
+        state.set_ball_collisions(true);
+        let mut ball = Ball::new(Point::new(300., 300.), -PI / 2., Speed::default());
+        ball.release();
+        state.balls = vec![ball];
+
+        // The split balls start on top of each other, but do not bounce off
+        // each other while they get apart
+        state.activate_bonus(BonusType::Divide);
+        assert_eq!(state.balls.len(), 3);
+        let headings: Vec<Vec2> = state.balls.iter().map(|b| b.velocity).collect();
+        for _ in 0..20 {
+            state.update(1. / 120.);
+        }
+        for (i, a) in state.balls.iter().enumerate() {
+            assert!((a.velocity - headings[i]).norm() < 1e-6);
+            assert_eq!(a.split(), None);
+            for b in &state.balls[i + 1..] {
+                assert!((a.position() - b.position()).norm() > 2. * BALL_RADIUS);
+            }
+        }
+    }
+
+    #[test]
+    fn test_warp() {
+        let mut state = State::default();
+        let right = Input {
+            direction: 1.,
+            fire: false,
+            release: false,
+        };
+
+        // The wall stops the paddle…
+        for _ in 0..240 {
+            state.input(right);
+            state.update(1. / 120.);
+        }
+        assert!(!state.warped());
+
+        // …until the gate is open
+        state.activate_bonus(BonusType::Break);
+        for _ in 0..240 {
+            state.input(right);
+            state.update(1. / 120.);
+        }
+        assert!(state.warped());
+        assert!(state.cleared());
+    }
+
+    #[test]
+    fn test_score() {
+        let mut state = State::default();
+        let target = state.player.position() + Point::new(-40. + BOLT_WIDTH, -300.);
+        let mut level = Level::default();
+        level.bricks = vec![
+            Brick::new(BrickType::Hard, target, 32., 16.),
+            Brick::new(BrickType::Super, Point::new(100., 100.), 32., 16.),
+        ];
+        state.load_level(level);
+        assert_eq!(state.score(), 0);
+
+        // Two shots to break a hard brick
+        state.fire_laser();
+        for _ in 0..120 {
+            state.update(1. / 120.);
+        }
+        assert_eq!(state.score(), 0);
+        state.fire_laser();
+        for _ in 0..120 {
+            state.update(1. / 120.);
+        }
+        assert_eq!(state.score(), u64::from(BrickType::Hard.points()));
+    }
+
+    #[test]
+    fn test_multiplier() {
+        assert_eq!(multiplier(0), 1);
+        assert_eq!(multiplier(COMBO_STEP - 1), 1);
+        assert_eq!(multiplier(COMBO_STEP), 2);
+        assert_eq!(multiplier(100), MAX_MULTIPLIER);
+    }
+
+    #[test]
+    fn test_same_seed_same_game() {
+        let mut a = State::new(Level::default(), 42);
+        let mut b = State::new(Level::default(), 42);
+
+        for _ in 0..1200 {
+            a.update(1. / 60.);
+            b.update(1. / 60.);
+        }
+
+        assert_eq!(a.bricks().len(), b.bricks().len());
+        assert_eq!(a.balls.len(), b.balls.len());
+        assert_eq!(
+            a.bonuses.iter().map(|b| b.bonus_type).collect::<Vec<_>>(),
+            b.bonuses.iter().map(|b| b.bonus_type).collect::<Vec<_>>()
+        );
+        assert_eq!(a.lives, b.lives);
+    }
 }
@@ -0,0 +1,40 @@
+use failure::{err_msg, Error};
+use sdl2::gfx::primitives::DrawRenderer;
+use sdl2::pixels::Color;
+use sdl2::render::{Canvas, RenderTarget};
+
+use resize::RenderContext;
+use utils::{Pixels, Point};
+
+/// Size of a glyph in the SDL_gfx built-in font
+const GLYPH_SIZE: f64 = 8.;
+
+/// Draw a line of text centered on `center`, with glyphs `size` pixels high
+pub fn draw_text<T>(
+    canvas: &mut Canvas<T>,
+    context: &RenderContext,
+    center: Point,
+    size: Pixels,
+    text: &str,
+    color: Color,
+) -> Result<(), Error>
+where
+    T: RenderTarget,
+{
+    // The font can't be scaled, so scale the whole canvas while drawing it
+    let scale = f64::from(context.scale(size)) / GLYPH_SIZE;
+    if scale <= 0. {
+        return Ok(());
+    }
+
+    let center = context.translate_point(center);
+    let x = center.x / scale - GLYPH_SIZE * text.len() as f64 / 2.;
+    let y = center.y / scale - GLYPH_SIZE / 2.;
+
+    canvas
+        .set_scale(scale as f32, scale as f32)
+        .map_err(err_msg)?;
+    let result = canvas.string(x as i16, y as i16, text, color);
+    canvas.set_scale(1., 1.).map_err(err_msg)?;
+    result.map_err(err_msg)
+}
@@ -87,6 +87,7 @@ pub enum PowerUpType {
 }
 
 #[allow(dead_code)]
+#[derive(Clone, Copy)]
 pub enum BackgroundSprite {
     Polygon,
     PolygonDark,
@@ -231,7 +232,6 @@ impl TextureMaker {
         CopyTool::new(Rect::new(x, y, w, h), dst)
     }
 
-    #[allow(dead_code)]
     pub fn rocket(sprite: RocketSprite, dst: Rect) -> CopyTool {
         let (y, w, h) = (79, 16, 40);
         let x = match sprite {
@@ -241,7 +241,6 @@ impl TextureMaker {
         CopyTool::new(Rect::new(x, y, w, h), dst)
     }
 
-    #[allow(dead_code)]
     pub fn background(sprite: BackgroundSprite, dst: Rect) -> CopyTool {
         let (w, h) = (64, 64);
         let xt = vec![0, 64, 128, 192, 256, 320];
@@ -271,7 +270,6 @@ impl TextureMaker {
         CopyTool::new(Rect::new(x, y, w, h), dst)
     }
 
-    #[allow(dead_code)]
     pub fn props(sprite: PropsSprite, dst: Rect) -> CopyTool {
         let (w, h): (u32, u32) = (16, 16);
         let xt = vec![256, 288, 320, 352, 384];
@@ -399,7 +397,6 @@ impl TextureMaker {
         CopyTool::new(Rect::new(x, y, w, h), dst)
     }
 
-    #[allow(dead_code)]
     pub fn powerups(sprite: PowerUpSprite, put: PowerUpType, dst: Rect) -> CopyTool {
         let (w, h) = (16, 16);
         let x = match put {
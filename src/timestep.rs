@@ -0,0 +1,84 @@
+use std::time::Duration;
+
+use traits::Updatable;
+
+/// Simulation rate, in steps per second
+pub const STEP_RATE: u32 = 120;
+
+/// Longest frame the accumulator will try to catch up on. Anything longer (a
+/// debugger pause, a window drag…) is dropped instead of simulated.
+const MAX_FRAME: Duration = Duration::from_millis(250);
+
+/// Fixed-timestep accumulator. Wall-clock time is fed frame by frame, and the
+/// simulation is stepped by a constant `dt` as many times as it fits. What is
+/// left over is exposed as an interpolation factor for rendering.
+pub struct FixedStep {
+    step: Duration,
+    accumulator: Duration,
+}
+
+impl FixedStep {
+    pub fn new(rate: u32) -> Self {
+        FixedStep {
+            step: Duration::from_nanos(1_000_000_000 / u64::from(rate)),
+            accumulator: Duration::new(0, 0),
+        }
+    }
+
+    /// Length of one simulation step, in seconds
+    pub fn dt(&self) -> f64 {
+        self.step.as_secs() as f64 + f64::from(self.step.subsec_nanos()) * 1e-9
+    }
+
+    /// Feed `frame` of elapsed time and step `target` accordingly. Returns the
+    /// number of steps that were run.
+    pub fn advance<U: Updatable>(&mut self, frame: Duration, target: &mut U) -> u32 {
+        self.accumulator += if frame > MAX_FRAME { MAX_FRAME } else { frame };
+
+        let dt = self.dt();
+        let mut steps = 0;
+        while self.accumulator >= self.step {
+            target.update(dt);
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+        steps
+    }
+
+    /// How far we are between the last step and the next one, in `[0, 1)`
+    pub fn alpha(&self) -> f64 {
+        self.accumulator.subsec_nanos() as f64 / self.step.subsec_nanos() as f64
+    }
+}
+
+impl Default for FixedStep {
+    fn default() -> Self {
+        Self::new(STEP_RATE)
+    }
+}
+
+#[cfg(test)]
+mod timestep_test {
+    use super::*;
+
+    struct Counter(u32);
+
+    impl Updatable for Counter {
+        fn update(&mut self, _dt: f64) {
+            self.0 += 1;
+        }
+    }
+
+    #[test]
+    fn test_accumulator() {
+        let mut stepper = FixedStep::new(100);
+        let mut counter = Counter(0);
+
+        assert_eq!(stepper.advance(Duration::from_millis(25), &mut counter), 2);
+        assert_eq!(stepper.alpha(), 0.5);
+        assert_eq!(stepper.advance(Duration::from_millis(5), &mut counter), 1);
+        assert_eq!(stepper.alpha(), 0.);
+        assert_eq!(stepper.advance(Duration::from_secs(10), &mut counter), 25);
+        assert_eq!(counter.0, 28);
+    }
+}
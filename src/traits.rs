@@ -1,15 +1,17 @@
+#[cfg(feature = "render")]
 use resize;
 use utils;
 
+#[cfg(feature = "render")]
 pub trait Renderable<T>
 where
     T: sdl2::render::RenderTarget,
 {
     fn render(
         &self,
-        &mut sdl2::render::Canvas<T>,
-        &resize::RenderContext,
-        &sdl2::render::Texture,
+        canvas: &mut sdl2::render::Canvas<T>,
+        context: &resize::RenderContext,
+        texture: &sdl2::render::Texture,
     ) -> Result<(), failure::Error>;
 }
 
@@ -17,8 +19,20 @@ pub trait Updatable {
     fn update(&mut self, dt: f64);
 }
 
-pub type Collision = (utils::Rad, utils::Pixels);
+/// Unit normal pushing out of the collision, and how deep it goes
+pub type Collision = (utils::Vec2, utils::Pixels);
 
 pub trait Collide<T> {
-    fn collide(&self, &T) -> Option<Collision>;
+    fn collide(&self, other: &T) -> Option<Collision>;
+}
+
+/// Time of impact, as a fraction of the motion, and the normal of the surface
+/// that was hit
+pub type Impact = (f64, utils::Vec2);
+
+pub trait Sweep<T> {
+    /// Check if `other`, moving by `motion` during this step, hits `self`.
+    /// Shapes that already overlap at the start of the motion hit at once,
+    /// unless they are moving apart.
+    fn sweep(&self, other: &T, motion: utils::Point) -> Option<Impact>;
 }
@@ -1,30 +1,66 @@
 pub use std::f64::consts::PI;
-use std::ops::{Add, BitOr, Mul, Sub};
+use std::ops::{Add, Mul, Neg, Sub};
 
 pub type Rad = f64;
 pub type Pixels = f64;
 
-#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
-pub struct Point {
+/// Cartesian 2D vector, used for velocities and collision normals
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct Vec2 {
     pub x: Pixels,
     pub y: Pixels,
 }
 
-impl Point {
+/// Positions are vectors from the top-left corner of the level
+pub type Point = Vec2;
+
+impl Vec2 {
     pub fn new(x: Pixels, y: Pixels) -> Self {
-        Point { x, y }
+        Vec2 { x, y }
+    }
+
+    /// Unit vector pointing in the `angle` direction
+    pub fn from_angle(angle: Rad) -> Self {
+        Vec2::new(angle.cos(), angle.sin())
     }
 
     pub fn norm(self) -> Pixels {
-        (self.x * self.x + self.y * self.y).sqrt()
+        self.dot(self).sqrt()
     }
 
     pub fn angle(self) -> Rad {
         self.y.atan2(self.x)
     }
+
+    pub fn dot(self, other: Self) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// Same direction with a length of one, or the null vector if it has no
+    /// direction
+    pub fn normalize(self) -> Self {
+        let norm = self.norm();
+        if norm == 0. {
+            self
+        } else {
+            self * (1. / norm)
+        }
+    }
+
+    /// Mirror the vector off a surface with the unit `normal`
+    pub fn reflect(self, normal: Self) -> Self {
+        self - normal * (2. * self.dot(normal))
+    }
+
+    /// Turn the vector by `angle`, clockwise on screen since the y axis points
+    /// down
+    pub fn rotate(self, angle: Rad) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Vec2::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+    }
 }
 
-impl Sub for Point {
+impl Sub for Vec2 {
     type Output = Self;
 
     fn sub(self, rhs: Self) -> Self {
@@ -35,7 +71,7 @@ impl Sub for Point {
     }
 }
 
-impl Add for Point {
+impl Add for Vec2 {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self {
@@ -46,79 +82,37 @@ impl Add for Point {
     }
 }
 
-impl From<Vector> for Point {
-    fn from(vector: Vector) -> Self {
-        Self {
-            x: vector.x(),
-            y: vector.y(),
-        }
-    }
-}
-
-impl Into<sdl2::rect::Point> for Point {
-    fn into(self) -> sdl2::rect::Point {
-        sdl2::rect::Point::new(self.x as i32, self.y as i32)
-    }
-}
-
-#[derive(Clone, Copy, Debug)]
-pub struct Vector {
-    pub angle: Rad,
-    pub norm: Pixels,
-}
-
-impl Vector {
-    pub fn x(&self) -> Pixels {
-        self.angle.cos() * self.norm
-    }
-
-    pub fn y(&self) -> Pixels {
-        self.angle.sin() * self.norm
-    }
-}
-
-impl From<Point> for Vector {
-    fn from(point: Point) -> Self {
-        Vector {
-            angle: point.angle(),
-            norm: point.norm(),
-        }
-    }
-}
-
-impl Add for Vector {
+impl Mul<Pixels> for Vec2 {
     type Output = Self;
 
-    fn add(self, rhs: Self) -> Self {
-        Vector::from(Point::from(self) + Point::from(rhs))
+    fn mul(self, rhs: Pixels) -> Self {
+        Self {
+            x: self.x * rhs,
+            y: self.y * rhs,
+        }
     }
 }
 
-impl Mul<Pixels> for Vector {
+impl Neg for Vec2 {
     type Output = Self;
 
-    fn mul(self, rhs: Pixels) -> Self {
-        Vector {
-            norm: self.norm * rhs,
-            ..self
-        }
+    fn neg(self) -> Self {
+        Vec2::new(-self.x, -self.y)
     }
 }
 
-impl BitOr<Rad> for Vector {
-    type Output = Self;
-
-    fn bitor(self, rhs: Rad) -> Self {
-        Vector {
-            angle: -self.angle + 2. * rhs + PI % (2. * PI),
-            ..self
-        }
+#[cfg(feature = "render")]
+impl Into<sdl2::rect::Point> for Point {
+    fn into(self) -> sdl2::rect::Point {
+        sdl2::rect::Point::new(self.x as i32, self.y as i32)
     }
 }
 
 #[cfg(test)]
 mod utils_test {
     use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_pcg::Pcg32;
 
     #[test]
     fn test_point_norm() {
@@ -135,14 +129,71 @@ mod utils_test {
         assert_eq!(Point::new(0., -1.).angle(), -PI / 2.);
     }
 
+    /// Random unit vector along with its angle
+    fn direction(rng: &mut Pcg32) -> (Vec2, Rad) {
+        let angle = rng.gen_range(-PI, PI);
+        (Vec2::from_angle(angle), angle)
+    }
+
+    fn close(a: Vec2, b: Vec2) -> bool {
+        (a - b).norm() < 1e-9
+    }
+
     #[test]
-    fn test_collision() {
-        let v = Vector {
-            norm: 1.0,
-            angle: PI / 4.,
-        };
-        let v = v | (PI / 2.);
-        assert_eq!(v.norm, 1.0);
-        assert_eq!(v.angle, 7. * PI / 4.);
+    fn test_rotate() {
+        let mut rng = Pcg32::seed_from_u64(0);
+        for _ in 0..1000 {
+            let (v, angle) = direction(&mut rng);
+            let turn = rng.gen_range(-4. * PI, 4. * PI);
+            let speed = rng.gen_range(1., 1000.);
+            let rotated = (v * speed).rotate(turn);
+            assert!((rotated.norm() - speed).abs() < 1e-9);
+            assert!(close(rotated.normalize(), Vec2::from_angle(angle + turn)));
+        }
+        assert!(close(Vec2::new(1., 0.).rotate(PI / 2.), Vec2::new(0., 1.)));
+    }
+
+    #[test]
+    fn test_reflect_conserves_speed() {
+        let mut rng = Pcg32::seed_from_u64(0);
+        for _ in 0..1000 {
+            let (v, _) = direction(&mut rng);
+            let (normal, _) = direction(&mut rng);
+            let v = v * rng.gen_range(1., 1000.);
+            let reflected = v.reflect(normal);
+            assert!((reflected.norm() - v.norm()).abs() < 1e-9);
+            // Reflecting twice gives the vector back
+            assert!(close(reflected.reflect(normal), v));
+        }
+    }
+
+    #[test]
+    fn test_reflect_walls() {
+        let mut rng = Pcg32::seed_from_u64(0);
+        // Normals of the top, bottom, left and right walls, pointing inside
+        let walls = [
+            Vec2::new(0., 1.),
+            Vec2::new(0., -1.),
+            Vec2::new(1., 0.),
+            Vec2::new(-1., 0.),
+        ];
+        for normal in &walls {
+            for _ in 0..1000 {
+                let (v, _) = direction(&mut rng);
+                let v = v * rng.gen_range(1., 1000.);
+                if v.dot(*normal) >= 0. {
+                    continue;
+                }
+                // Heads back inside, keeping the same speed along the wall
+                let reflected = v.reflect(*normal);
+                assert!((reflected.dot(*normal) + v.dot(*normal)).abs() < 1e-9);
+                let along = Vec2::new(normal.y, -normal.x);
+                assert!((reflected.dot(along) - v.dot(along)).abs() < 1e-9);
+            }
+        }
+
+        // Coming down at 45 degrees on the bottom wall
+        let v = Vec2::from_angle(PI / 4.).reflect(Vec2::new(0., -1.));
+        assert!(close(v, Vec2::from_angle(-PI / 4.)));
     }
 }
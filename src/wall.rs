@@ -1,14 +1,20 @@
+#[cfg(feature = "render")]
 use failure::err_msg;
+#[cfg(feature = "render")]
 use sdl2::rect::Rect;
+#[cfg(feature = "render")]
 use sdl2::render::{Canvas, RenderTarget, Texture};
 
+#[cfg(feature = "render")]
 use resize::RenderContext;
 use shape::{InfiniteWall, WallOrientation};
+#[cfg(feature = "render")]
 use traits::Renderable;
 use utils::{Pixels, Point};
 
 pub const WALL_THICKNESS: Pixels = 4.0;
 
+#[cfg_attr(not(feature = "render"), allow(dead_code))]
 pub struct Wall {
     width: Pixels,
     height: Pixels,
@@ -23,6 +29,7 @@ impl Wall {
             shape: InfiniteWall {
                 orientation: WallOrientation::Top,
                 center: Point::new(width / 2., WALL_THICKNESS / 2.),
+                gap: None,
             },
         }
     }
@@ -34,6 +41,7 @@ impl Wall {
             shape: InfiniteWall {
                 orientation: WallOrientation::Left,
                 center: Point::new(WALL_THICKNESS / 2., height / 2.),
+                gap: None,
             },
         }
     }
@@ -45,6 +53,7 @@ impl Wall {
             shape: InfiniteWall {
                 orientation: WallOrientation::Right,
                 center: Point::new(width - (WALL_THICKNESS / 2.), height / 2.),
+                gap: None,
             },
         };
     }
@@ -56,6 +65,7 @@ impl Wall {
             shape: InfiniteWall {
                 orientation: WallOrientation::Bottom,
                 center: Point::new(width / 2., height - (WALL_THICKNESS / 2.)),
+                gap: None,
             },
         }
     }
@@ -63,8 +73,58 @@ impl Wall {
     pub fn make_walls(h: Pixels, w: Pixels) -> Vec<Self> {
         return vec![Wall::top(w), Wall::left(h), Wall::right(h, w)];
     }
+
+    /// Open the wall between `from` and `to`, along its length
+    pub fn open(&mut self, from: Pixels, to: Pixels) {
+        self.shape.gap = Some((from, to));
+    }
+
+    /// Parts of the wall on each side of the gap, as their center, width and
+    /// height
+    #[cfg(feature = "render")]
+    fn segments(&self) -> Vec<(Point, Pixels, Pixels)> {
+        let center = self.shape.center;
+        let (start, end) = match self.shape.gap {
+            Some(gap) => gap,
+            None => return vec![(center, self.width, self.height)],
+        };
+
+        match self.shape.orientation {
+            WallOrientation::Left | WallOrientation::Right => {
+                let (top, bottom) = (center.y - self.height / 2., center.y + self.height / 2.);
+                vec![
+                    (
+                        Point::new(center.x, (top + start) / 2.),
+                        self.width,
+                        f64::max(start - top, 0.),
+                    ),
+                    (
+                        Point::new(center.x, (end + bottom) / 2.),
+                        self.width,
+                        f64::max(bottom - end, 0.),
+                    ),
+                ]
+            }
+            WallOrientation::Top | WallOrientation::Bottom => {
+                let (left, right) = (center.x - self.width / 2., center.x + self.width / 2.);
+                vec![
+                    (
+                        Point::new((left + start) / 2., center.y),
+                        f64::max(start - left, 0.),
+                        self.height,
+                    ),
+                    (
+                        Point::new((end + right) / 2., center.y),
+                        f64::max(right - end, 0.),
+                        self.height,
+                    ),
+                ]
+            }
+        }
+    }
 }
 
+#[cfg(feature = "render")]
 impl<T> Renderable<T> for Wall
 where
     T: RenderTarget,
@@ -76,13 +136,15 @@ where
         _texture: &Texture,
     ) -> Result<(), failure::Error> {
         canvas.set_draw_color(sdl2::pixels::Color::RGBA(127, 127, 127, 255));
-        canvas
-            .fill_rect(Rect::from_center(
-                context.translate_point(self.shape.center),
-                context.scale(self.width),
-                context.scale(self.height),
-            ))
-            .map_err(err_msg)?;
+        for (center, width, height) in self.segments() {
+            canvas
+                .fill_rect(Rect::from_center(
+                    context.translate_point(center),
+                    context.scale(width),
+                    context.scale(height),
+                ))
+                .map_err(err_msg)?;
+        }
         Ok(())
     }
 }
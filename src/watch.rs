@@ -0,0 +1,83 @@
+use std::fs;
+use std::time::SystemTime;
+
+use failure::Error;
+
+use level::Level;
+
+/// Watches a level file for changes by polling its modification time
+pub struct LevelWatcher {
+    filename: String,
+    modified: Option<SystemTime>,
+}
+
+impl LevelWatcher {
+    pub fn new(filename: &str) -> Self {
+        LevelWatcher {
+            filename: filename.to_string(),
+            modified: LevelWatcher::mtime(filename),
+        }
+    }
+
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    fn mtime(filename: &str) -> Option<SystemTime> {
+        fs::metadata(filename).and_then(|m| m.modified()).ok()
+    }
+
+    /// Reload the level if the file changed since the last call
+    pub fn poll(&mut self) -> Option<Result<Level, Error>> {
+        let modified = LevelWatcher::mtime(&self.filename);
+        if modified == self.modified {
+            return None;
+        }
+
+        self.modified = modified;
+        Some(Level::load_file(&self.filename))
+    }
+}
+
+#[cfg(test)]
+mod watch_test {
+    use super::*;
+    use std::env;
+    use std::fs::File;
+    use std::io::Write;
+    use std::time::Duration;
+
+    fn write(filename: &str, contents: &str, modified: SystemTime) {
+        let mut file = File::create(filename).unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        file.set_modified(modified).unwrap();
+    }
+
+    fn level(width: u32) -> String {
+        format!(
+            r#"{{"bricks": [{{"center": {{"x": 50, "y": 20}}, "width": 32, "height": 16,
+                "breakable": true, "hitpoints": 1}}], "width": {}, "height": 100}}"#,
+            width
+        )
+    }
+
+    #[test]
+    fn test_poll() {
+        let path = env::temp_dir().join("arkanoid_watch_test.json");
+        let filename = path.to_str().unwrap();
+        let start = SystemTime::now();
+        write(filename, &level(100), start);
+
+        let mut watcher = LevelWatcher::new(filename);
+        assert!(watcher.poll().is_none());
+
+        write(filename, &level(200), start + Duration::from_secs(1));
+        assert_eq!(watcher.poll().unwrap().unwrap().width(), 200);
+        assert!(watcher.poll().is_none());
+
+        write(filename, "{", start + Duration::from_secs(2));
+        assert!(watcher.poll().unwrap().is_err());
+
+        fs::remove_file(filename).unwrap();
+    }
+}